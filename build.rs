@@ -1,5 +1,9 @@
 fn main() {
-    let mut res = winres::WindowsResource::new();
-    res.set_icon("icon.ico");
-    res.compile().expect("Failed to compile Windows resources");
+    // アイコンリソースは Windows ターゲットの GUI ビルドのときだけ埋め込む
+    #[cfg(feature = "gui")]
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
+        let mut res = winres::WindowsResource::new();
+        res.set_icon("icon.ico");
+        res.compile().expect("Failed to compile Windows resources");
+    }
 }
@@ -0,0 +1,729 @@
+//! Headless `render` subcommand: writes PNG/ICO files without opening a window.
+
+use icon_marker::spec::is_feature_tag;
+use icon_marker::{fill, font};
+use icon_marker::{
+    png_meta, project, render, render_sizes, write_ico, Color, Effect, EffectKind, Fill, FontSpec,
+    GradientStop, IconSpec, ImageFill, ImageLayer, Layer, LayerContent,
+};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+Usage: icon-marker render [OPTIONS] --out <PATH>
+
+Renders an icon without opening the GUI. The output format is taken from the
+extension of --out (.png or .ico).
+
+Options:
+  --project <PATH>       Start from a saved project file, or a PNG exported by
+                         IconMarker; other options override it
+  --text <TEXT>          Text to draw (default: G); \\n starts a new line and
+                         \\\\ stands for a backslash
+  --writing-mode <MODE>  horizontal, or vertical (tategaki: top to bottom,
+                         columns right to left)
+  --align <ALIGN>        Alignment of multi-line text: left, center, right
+                         (top, center, bottom in vertical text)
+  --line-height <N>      Line spacing as a multiple of the font's (default 1.0)
+  --font-family <NAME>   Installed font family (default: bundled Aoboshi One)
+  --font-style <NAME>    Style within --font-family, e.g. Bold
+  --font-file <PATH>     Font file to use instead of an installed family
+  --font-index <N>       Face within a font collection (.ttc), default 0
+  --fallback <FONT>      Font for characters the main font lacks: a family name
+                         or a font file path; repeat to build a chain
+  --feature <TAG>        OpenType feature toggle: liga, +ss01, -kern, liga=off;
+                         repeat for several features
+  --bg <COLOR>           Background colour, #RRGGBB
+  --bg-gradient <TYPE>   Background gradient type: linear, radial, conic
+  --bg-gradient-angle <DEG>, --bg-stop <OFFSET:COLOR>, --bg-gradient-space <S>
+                         Like --gradient-angle, --stop and --gradient-space,
+                         for the background
+  --bg-mesh <TL,TR,BR,BL>
+                         Background mesh gradient: colours of the top-left,
+                         top-right, bottom-right and bottom-left corners
+  --shape <SHAPE>        Background plate: square, rounded, circle, squircle,
+                         none; outside it the icon is transparent
+  --corner-radius <FRACTION>
+                         Corner radius of the rounded plate, 0.0-0.5 (default 0.2)
+  --image <PATH>         Add an image layer (PNG, JPEG), e.g. a logo; repeat
+                         for several. The --image-* options below set the
+                         last one added
+  --image-fit <FIT>      cover (default), contain, stretch
+  --image-offset <X,Y>   Shift right and down as fractions of the icon
+  --image-scale <N>      Size relative to the fitted size (default 1.0)
+  --image-opacity <N>    Layer opacity, 0.0-1.0
+  --image-blend <MODE>   normal, multiply, screen, overlay, soft-light
+  --image-clip <on|off>  Show the image only on the background plate
+  --image-above <on|off> Put the layer over the text instead of behind it
+  --fill-color <COLOR>   Fill the text with a solid colour
+  --fill-pattern <NAME>  Fill the text with a tiled pattern (see --pattern)
+  --pattern-colors <FG,BG>
+                         Line and background colours of --fill-pattern
+  --fill-image <PATH>    Fill the text with an image (PNG, JPEG)
+  --fill-mesh <TL,TR,BR,BL>
+                         Fill the text with a four-corner mesh gradient
+  --gradient <TYPE>      Text gradient type: linear, radial, conic
+  --gradient-angle <DEG> Gradient direction, clockwise from up (default 45)
+  --stop <OFFSET:COLOR>  Gradient colour stop, e.g. 0.5:#FF8800; repeat for
+                         several stops (replaces the default two)
+  --grad-start <COLOR>   Colour of the first gradient stop
+  --grad-end <COLOR>     Colour of the last gradient stop
+  --gradient-space <S>   Gradient (or mesh) interpolation: srgb, linear, oklab;
+                         give it after the option that sets the gradient
+  --stroke-width <FRACTION>
+                         Text outline width as a fraction of the icon, 0.0-0.1
+  --stroke-color <COLOR> Outline colour
+  --stroke-align <ALIGN> Outline position: inside, center, outside (default)
+  --effect <KIND>        Add an effect layer: drop-shadow, inner-shadow,
+                         outer-glow, long-shadow; repeat for several. The
+                         --effect-* options below set the last one added
+  --effect-offset <X,Y>  Shift right and down as fractions of the icon, each
+                         -1.0-1.0 (long shadow: direction and length)
+  --effect-blur <FRACTION>
+                         Blur radius as a fraction of the icon, 0.0-0.2
+  --effect-spread <FRACTION>
+                         Grow the shape before blurring, 0.0-0.1
+  --effect-color <COLOR> Effect colour
+  --effect-opacity <N>   Effect opacity, 0.0-1.0
+  --effect-clip <on|off> Show the effect only on the background plate
+                         (default off)
+  --bevel <FRACTION>     Bevel the text with a rim this wide, 0.0-0.1
+  --bevel-style <STYLE>  inner (default), emboss
+  --bevel-depth <N>      Strength of the bevel shading, 0.0-1.0
+  --bevel-angle <DEG>    Light direction, clockwise from up (default 315)
+  --bevel-soften <FRACTION>
+                         Round off the bevel
+  --bevel-colors <HI,SH> Highlight and shadow colours of the bevel
+  --extrude <FRACTION>   Give the text solid 3D sides this deep, 0.0-0.2
+  --extrude-angle <DEG>  Direction of the sides, clockwise from up (default 135)
+  --extrude-color <COLOR>
+                         Colour of the sides
+  --extrude-shade <N>    How much darker the far end of the sides is, 0.0-1.0
+  --blending <MODE>      Text edge compositing: gamma, linear (linear light)
+  --padding <FRACTION>   Margin as a fraction of the icon, 0.0-0.4
+  --pattern <NAME>       Background pattern: chevron, stripes, dots, grid,
+                         hexagons, waves, checkerboard, noise, rings, none
+  --pattern-density <N>  Pattern lines per pixel relative to its default, e.g. 2
+  --pattern-angle <DEG>  Rotate the pattern clockwise
+  --pattern-line-width <PX>
+                         Width of the pattern's lines in pixels of a 256px
+                         icon (default 1); patterns scale with the icon
+  --pattern-highlight <N>
+                         How much the lines lighten the background, 0-255
+  --pattern-shadow <N>   How much the shadow under the lines darkens, 0-255
+  --pattern-min-size <N> Below this icon size the pattern stops shrinking and
+                         shows fewer lines instead (default 128)
+  --pattern-hide-small <on|off>
+                         Leave the pattern out below --pattern-min-size
+  --antialias <on|off>   Anti-alias glyph edges (default on)
+  --supersample <N>      Rasterize glyphs at N times the size, 1-8 (default 1)
+  --snap-stems <on|off>  Snap stems to the pixel grid at 48px and below
+  --sizes <N,N,...>      ICO entry sizes; a PNG uses the largest (default 256)
+  --out <PATH>           Output file
+  -h, --help             Print this help";
+
+enum CliError {
+    /// Bad command line; exit code 2.
+    Usage(String),
+    /// Rendering or writing failed; exit code 1.
+    Failed(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) | CliError::Failed(msg) => f.write_str(msg),
+        }
+    }
+}
+
+enum OutputKind {
+    Png,
+    Ico,
+}
+
+struct RenderArgs {
+    spec: IconSpec,
+    /// Set when `--sizes` was given explicitly.
+    sizes_given: bool,
+    out: PathBuf,
+}
+
+/// Runs the `render` subcommand and returns the process exit code.
+pub fn main(args: &[String]) -> i32 {
+    match run(args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {e}");
+            match e {
+                CliError::Usage(_) => {
+                    eprintln!("\n{USAGE}");
+                    2
+                }
+                CliError::Failed(_) => 1,
+            }
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), CliError> {
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    let parsed = parse_args(args)?;
+    let spec = &parsed.spec;
+
+    let kind = match parsed
+        .out
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("png") => OutputKind::Png,
+        Some("ico") => OutputKind::Ico,
+        _ => {
+            return Err(CliError::Usage(format!(
+                "cannot tell output format from {:?}; use a .png or .ico extension",
+                parsed.out.display()
+            )))
+        }
+    };
+
+    font::resolve_chain(spec).map_err(|e| CliError::Failed(e.to_string()))?;
+    let fill_images = [&spec.background, &spec.fill, &spec.stroke.fill]
+        .into_iter()
+        .filter_map(|f| match f {
+            Fill::Image(image) => Some(&image.path),
+            _ => None,
+        });
+    let layer_images = spec.layers.iter().filter_map(|l| match &l.content {
+        LayerContent::Image { image } => Some(&image.path),
+        _ => None,
+    });
+    for path in fill_images.chain(layer_images) {
+        fill::load_image(path).map_err(|e| CliError::Failed(format!("{}: {e}", path.display())))?;
+    }
+
+    match kind {
+        OutputKind::Png => {
+            let size = if parsed.sizes_given {
+                spec.sizes.iter().copied().max().unwrap_or(256)
+            } else {
+                256
+            };
+            png_meta::save_png(&parsed.out, &render(spec, size), spec)
+                .map_err(|e| CliError::Failed(format!("{}: {e}", parsed.out.display())))?;
+        }
+        OutputKind::Ico => {
+            if let Some(&s) = spec.sizes.iter().find(|&&s| s > 256) {
+                return Err(CliError::Usage(format!(
+                    "ICO entries cannot be larger than 256 (got {s})"
+                )));
+            }
+            write_ico(&parsed.out, &render_sizes(spec))
+                .map_err(|e| CliError::Failed(format!("{}: {e}", parsed.out.display())))?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<RenderArgs, CliError> {
+    let mut spec = IconSpec::default();
+    let mut sizes_given = false;
+    let mut stops_given = false;
+    let mut bg_stops_given = false;
+    let mut out = None;
+    // --effect / --image で足すレイヤーと、文字より上に置くかどうか
+    let mut added: Vec<(Layer, bool)> = Vec::new();
+    let mut last_effect = None;
+    let mut last_image = None;
+
+    // --project は他のオプションより先に読み込み、個別指定で上書きできるようにする
+    if let Some(path) = find_project_arg(args)? {
+        let is_png = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+        let loaded = if is_png {
+            png_meta::load_spec(&path).map_err(|e| e.to_string())
+        } else {
+            project::load(&path).map_err(|e| e.to_string())
+        };
+        spec = loaded.map_err(|e| CliError::Failed(format!("{}: {e}", path.display())))?;
+        sizes_given = true;
+    }
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        // --key=value と --key value の両方を受け付ける
+        let (key, inline) = match arg.split_once('=') {
+            Some((k, v)) if k.starts_with("--") => (k, Some(v.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| iter.next().cloned())
+                .ok_or_else(|| CliError::Usage(format!("{key} needs a value")))
+        };
+        let invalid = |e: &dyn fmt::Display| CliError::Usage(format!("{key}: {e}"));
+
+        match key {
+            "--project" => {
+                value()?;
+            }
+            "--text" => spec.text = unescape(&value()?),
+            "--writing-mode" => spec.writing_mode = value()?.parse().map_err(|e| invalid(&e))?,
+            "--align" => spec.text_align = value()?.parse().map_err(|e| invalid(&e))?,
+            "--line-height" => {
+                let line_height: f32 = value()?.parse().map_err(|e| invalid(&e))?;
+                if !(line_height > 0.0 && line_height.is_finite()) {
+                    return Err(invalid(&format!("{line_height} must be positive")));
+                }
+                spec.line_height = line_height;
+            }
+            "--font-family" => {
+                spec.font.family = Some(value()?);
+                spec.font.path = None;
+            }
+            "--font-style" => spec.font.style = Some(value()?),
+            "--font-file" => spec.font = FontSpec::file_face(value()?, spec.font.index),
+            "--fallback" => {
+                let v = value()?;
+                let fallback = if Path::new(&v).is_file() {
+                    FontSpec::file(v)
+                } else {
+                    FontSpec::family(v, None)
+                };
+                spec.fallback_fonts.push(fallback);
+            }
+            "--feature" => {
+                let (tag, on) = parse_feature(&value()?).map_err(|e| invalid(&e))?;
+                spec.font_features.insert(tag, on);
+            }
+            "--font-index" => spec.font.index = value()?.parse().map_err(|e| invalid(&e))?,
+            "--bg" => {
+                spec.background = Fill::Solid {
+                    color: value()?.parse().map_err(|e| invalid(&e))?,
+                }
+            }
+            "--shape" => spec.bg_shape = value()?.parse().map_err(|e| invalid(&e))?,
+            "--corner-radius" => {
+                spec.corner_radius = parse_fraction(&value()?, 0.5).map_err(|e| invalid(&e))?
+            }
+            "--fill-color" => {
+                spec.fill = Fill::Solid {
+                    color: value()?.parse().map_err(|e| invalid(&e))?,
+                }
+            }
+            "--fill-pattern" => {
+                spec.fill.pattern_mut().pattern = value()?.parse().map_err(|e| invalid(&e))?
+            }
+            "--pattern-colors" => {
+                let v = value()?;
+                let (fg, bg) = v
+                    .split_once(',')
+                    .ok_or_else(|| invalid(&"expected FG,BG"))?;
+                // 単色やグラデーションの塗りを黙って模様に置き換えない
+                let Fill::Pattern(pattern) = &mut spec.fill else {
+                    return Err(invalid(&"the fill is not a pattern"));
+                };
+                pattern.color = fg.trim().parse().map_err(|e| invalid(&e))?;
+                pattern.background = bg.trim().parse().map_err(|e| invalid(&e))?;
+            }
+            "--fill-image" => {
+                spec.fill = Fill::Image(ImageFill {
+                    path: value()?.into(),
+                })
+            }
+            "--gradient" | "--bg-gradient" => {
+                fill_for(&mut spec, key).gradient_mut().kind =
+                    value()?.parse().map_err(|e| invalid(&e))?
+            }
+            "--gradient-angle" | "--bg-gradient-angle" => {
+                fill_for(&mut spec, key).gradient_mut().angle =
+                    value()?.parse().map_err(|e| invalid(&e))?
+            }
+            "--stop" | "--bg-stop" => {
+                let stop = parse_stop(&value()?).map_err(|e| invalid(&e))?;
+                // 最初の --stop で既定（またはプロジェクト）のストップを置き換える
+                let given = if key == "--stop" {
+                    &mut stops_given
+                } else {
+                    &mut bg_stops_given
+                };
+                let gradient = fill_for(&mut spec, key).gradient_mut();
+                if !*given {
+                    gradient.stops.clear();
+                    *given = true;
+                }
+                gradient.stops.push(stop);
+            }
+            "--fill-mesh" | "--bg-mesh" => {
+                fill_for(&mut spec, key).mesh_mut().corners =
+                    parse_mesh(&value()?).map_err(|e| invalid(&e))?
+            }
+            "--grad-start" | "--grad-end" => {
+                let color = value()?.parse().map_err(|e| invalid(&e))?;
+                let Fill::Gradient(gradient) = &mut spec.fill else {
+                    return Err(invalid(&"the fill is not a gradient"));
+                };
+                let stops = &mut gradient.stops;
+                stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+                let (offset, stop) = if key == "--grad-start" {
+                    (0.0, stops.first_mut())
+                } else {
+                    (1.0, stops.last_mut())
+                };
+                match stop {
+                    Some(stop) => stop.color = color,
+                    None => stops.push(GradientStop::new(offset, color)),
+                }
+            }
+            "--gradient-space" | "--bg-gradient-space" => {
+                let space = value()?.parse().map_err(|e| invalid(&e))?;
+                // 単色や模様の塗りを黙ってグラデーションに置き換えない
+                match fill_for(&mut spec, key) {
+                    Fill::Mesh(mesh) => mesh.space = space,
+                    Fill::Gradient(gradient) => gradient.space = space,
+                    _ => return Err(invalid(&"the fill is not a gradient or mesh")),
+                }
+            }
+            "--stroke-width" => {
+                let width: f32 = value()?.parse().map_err(|e| invalid(&e))?;
+                if !(0.0..=0.1).contains(&width) {
+                    return Err(invalid(&format!("{width} is outside 0.0-0.1")));
+                }
+                spec.stroke.width = width;
+            }
+            "--stroke-color" => {
+                spec.stroke.fill = Fill::Solid {
+                    color: value()?.parse().map_err(|e| invalid(&e))?,
+                }
+            }
+            "--stroke-align" => spec.stroke.align = value()?.parse().map_err(|e| invalid(&e))?,
+            "--effect" => {
+                let kind: EffectKind = value()?.parse().map_err(|e| invalid(&e))?;
+                let effect = Effect::new(kind);
+                added.push((
+                    Layer::new(LayerContent::Effect { effect }),
+                    kind == EffectKind::InnerShadow,
+                ));
+                last_effect = Some(added.len() - 1);
+            }
+            "--effect-offset" | "--effect-blur" | "--effect-spread" | "--effect-color"
+            | "--effect-opacity" | "--effect-clip" => {
+                let v = value()?;
+                let i = last_effect.ok_or_else(|| invalid(&"no --effect given before it"))?;
+                let layer = &mut added[i].0;
+                let LayerContent::Effect { effect } = &mut layer.content else {
+                    unreachable!("--effect adds effect layers");
+                };
+                match key {
+                    "--effect-offset" => {
+                        let (x, y) = v.split_once(',').ok_or_else(|| invalid(&"expected X,Y"))?;
+                        let mut offset = [0.0; 2];
+                        for (o, v) in offset.iter_mut().zip([x, y]) {
+                            *o = v.trim().parse().map_err(|e| invalid(&e))?;
+                            if !(-Effect::MAX_OFFSET..=Effect::MAX_OFFSET).contains(o) {
+                                return Err(invalid(&format!(
+                                    "{o} is outside -{m}-{m}",
+                                    m = Effect::MAX_OFFSET
+                                )));
+                            }
+                        }
+                        effect.offset = offset;
+                    }
+                    "--effect-blur" => {
+                        effect.blur =
+                            parse_fraction(&v, Effect::MAX_BLUR).map_err(|e| invalid(&e))?
+                    }
+                    "--effect-spread" => {
+                        effect.spread =
+                            parse_fraction(&v, Effect::MAX_SPREAD).map_err(|e| invalid(&e))?
+                    }
+                    "--effect-color" => effect.color = v.parse().map_err(|e| invalid(&e))?,
+                    "--effect-clip" => layer.clip = parse_switch(&v).map_err(|e| invalid(&e))?,
+                    _ => {
+                        let opacity: f32 = v.parse().map_err(|e| invalid(&e))?;
+                        if !(0.0..=1.0).contains(&opacity) {
+                            return Err(invalid(&format!("{opacity} is outside 0.0-1.0")));
+                        }
+                        effect.opacity = opacity;
+                    }
+                }
+            }
+            "--image" => {
+                let image = ImageLayer {
+                    path: value()?.into(),
+                    ..ImageLayer::default()
+                };
+                added.push((Layer::new(LayerContent::Image { image }), false));
+                last_image = Some(added.len() - 1);
+            }
+            "--image-fit" | "--image-offset" | "--image-scale" | "--image-opacity"
+            | "--image-blend" | "--image-clip" | "--image-above" => {
+                let v = value()?;
+                let (layer, above) = last_image
+                    .map(|i| &mut added[i])
+                    .ok_or_else(|| invalid(&"no --image given before it"))?;
+                let LayerContent::Image { image } = &mut layer.content else {
+                    unreachable!("--image adds image layers");
+                };
+                match key {
+                    "--image-fit" => image.fit = v.parse().map_err(|e| invalid(&e))?,
+                    "--image-offset" => {
+                        let (x, y) = v.split_once(',').ok_or_else(|| invalid(&"expected X,Y"))?;
+                        image.offset = [
+                            x.trim().parse().map_err(|e| invalid(&e))?,
+                            y.trim().parse().map_err(|e| invalid(&e))?,
+                        ];
+                    }
+                    "--image-scale" => {
+                        let scale: f32 = v.parse().map_err(|e| invalid(&e))?;
+                        if !(scale > 0.0 && scale <= 8.0) {
+                            return Err(invalid(&format!("{scale} is outside 0.0-8.0")));
+                        }
+                        image.scale = scale;
+                    }
+                    "--image-opacity" => {
+                        layer.opacity = parse_fraction(&v, 1.0).map_err(|e| invalid(&e))?
+                    }
+                    "--image-blend" => layer.blend = v.parse().map_err(|e| invalid(&e))?,
+                    "--image-clip" => layer.clip = parse_switch(&v).map_err(|e| invalid(&e))?,
+                    _ => *above = parse_switch(&v).map_err(|e| invalid(&e))?,
+                }
+            }
+            "--bevel" => {
+                spec.bevel.size = parse_fraction(&value()?, 0.1).map_err(|e| invalid(&e))?
+            }
+            "--bevel-style" => spec.bevel.style = value()?.parse().map_err(|e| invalid(&e))?,
+            "--bevel-depth" => {
+                spec.bevel.depth = parse_fraction(&value()?, 1.0).map_err(|e| invalid(&e))?
+            }
+            "--bevel-angle" => spec.bevel.angle = value()?.parse().map_err(|e| invalid(&e))?,
+            "--bevel-soften" => spec.bevel.soften = value()?.parse().map_err(|e| invalid(&e))?,
+            "--bevel-colors" => {
+                let v = value()?;
+                let (hi, sh) = v
+                    .split_once(',')
+                    .ok_or_else(|| invalid(&"expected HI,SH"))?;
+                spec.bevel.highlight = hi.trim().parse().map_err(|e| invalid(&e))?;
+                spec.bevel.shadow = sh.trim().parse().map_err(|e| invalid(&e))?;
+            }
+            "--extrude" => {
+                spec.extrusion.depth = parse_fraction(&value()?, 0.2).map_err(|e| invalid(&e))?
+            }
+            "--extrude-angle" => {
+                spec.extrusion.angle = value()?.parse().map_err(|e| invalid(&e))?
+            }
+            "--extrude-color" => {
+                spec.extrusion.color = value()?.parse().map_err(|e| invalid(&e))?
+            }
+            "--extrude-shade" => {
+                spec.extrusion.shade = parse_fraction(&value()?, 1.0).map_err(|e| invalid(&e))?
+            }
+            "--blending" => spec.blending = value()?.parse().map_err(|e| invalid(&e))?,
+            "--padding" => {
+                let v = value()?;
+                let padding: f32 = v.parse().map_err(|e| invalid(&e))?;
+                if !(0.0..=0.4).contains(&padding) {
+                    return Err(invalid(&format!("{padding} is outside 0.0-0.4")));
+                }
+                spec.padding = padding;
+            }
+            "--pattern" => spec.pattern = value()?.parse().map_err(|e| invalid(&e))?,
+            "--pattern-density" => {
+                let density: f32 = value()?.parse().map_err(|e| invalid(&e))?;
+                if density <= 0.0 {
+                    return Err(invalid(&format!("{density} is not positive")));
+                }
+                spec.pattern_params.density = density;
+            }
+            "--pattern-angle" => {
+                spec.pattern_params.angle = value()?.parse().map_err(|e| invalid(&e))?
+            }
+            "--pattern-line-width" => {
+                spec.pattern_params.line_width =
+                    parse_fraction(&value()?, 16.0).map_err(|e| invalid(&e))?
+            }
+            "--pattern-highlight" => {
+                spec.pattern_params.highlight = value()?.parse().map_err(|e| invalid(&e))?
+            }
+            "--pattern-shadow" => {
+                spec.pattern_params.shadow = value()?.parse().map_err(|e| invalid(&e))?
+            }
+            "--pattern-min-size" => {
+                spec.pattern_params.min_size = value()?.parse().map_err(|e| invalid(&e))?
+            }
+            "--pattern-hide-small" => {
+                spec.pattern_params.hide_small = parse_switch(&value()?).map_err(|e| invalid(&e))?
+            }
+            "--antialias" => spec.antialias = parse_switch(&value()?).map_err(|e| invalid(&e))?,
+            "--supersample" => {
+                let n: u32 = value()?.parse().map_err(|e| invalid(&e))?;
+                if !(1..=IconSpec::MAX_SUPERSAMPLE).contains(&n) {
+                    return Err(invalid(&format!(
+                        "{n} is outside 1-{}",
+                        IconSpec::MAX_SUPERSAMPLE
+                    )));
+                }
+                spec.supersample = n;
+            }
+            "--snap-stems" => spec.snap_stems = parse_switch(&value()?).map_err(|e| invalid(&e))?,
+            "--sizes" => {
+                spec.sizes = parse_sizes(&value()?).map_err(|e| invalid(&e))?;
+                sizes_given = true;
+            }
+            "--out" | "-o" => out = Some(PathBuf::from(value()?)),
+            _ => return Err(CliError::Usage(format!("unexpected argument {arg:?}"))),
+        }
+    }
+
+    let out = out.ok_or_else(|| CliError::Usage("--out is required".to_string()))?;
+    // スタイルはファミリーの中から選ぶので、ファミリーなしでは無視されてしまう
+    if spec.font.style.is_some() && spec.font.family.is_none() {
+        return Err(CliError::Usage(
+            "--font-style needs --font-family".to_string(),
+        ));
+    }
+    // 文字の上に置くレイヤーは直上に差し込むので、後のものから入れて順番を保つ
+    let (above, below): (Vec<_>, Vec<_>) = added.into_iter().partition(|(_, above)| *above);
+    for (layer, _) in below {
+        spec.insert_layer(layer, false);
+    }
+    for (layer, _) in above.into_iter().rev() {
+        spec.insert_layer(layer, true);
+    }
+    Ok(RenderArgs {
+        spec,
+        sizes_given,
+        out,
+    })
+}
+
+fn find_project_arg(args: &[String]) -> Result<Option<PathBuf>, CliError> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some(path) = arg.strip_prefix("--project=") {
+            return Ok(Some(PathBuf::from(path)));
+        }
+        if arg == "--project" {
+            return match iter.next() {
+                Some(path) => Ok(Some(PathBuf::from(path))),
+                None => Err(CliError::Usage("--project needs a value".to_string())),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// Parses `tag`, `+tag`, `-tag` or `tag=on|off|1|0`.
+fn parse_feature(s: &str) -> Result<(String, bool), String> {
+    let (tag, on) = if let Some(tag) = s.strip_prefix('-') {
+        (tag, false)
+    } else if let Some((tag, value)) = s.split_once('=') {
+        let on = parse_switch(value).map_err(|_| format!("invalid feature value {value:?}"))?;
+        (tag, on)
+    } else {
+        (s.strip_prefix('+').unwrap_or(s), true)
+    };
+    if !is_feature_tag(tag) {
+        return Err(format!("{tag:?} is not a 4-character OpenType feature tag"));
+    }
+    Ok((tag.to_string(), on))
+}
+
+/// The fill an option sets: the background's for `--bg-*`, else the text's.
+fn fill_for<'a>(spec: &'a mut IconSpec, key: &str) -> &'a mut Fill {
+    if key.starts_with("--bg-") {
+        &mut spec.background
+    } else {
+        &mut spec.fill
+    }
+}
+
+fn parse_mesh(s: &str) -> Result<[Color; 4], String> {
+    let colors = s
+        .split(',')
+        .map(|c| c.trim().parse::<Color>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    colors
+        .try_into()
+        .map_err(|_| format!("expected four colours TL,TR,BR,BL, got {s:?}"))
+}
+
+/// A number in `0.0..=max`.
+fn parse_fraction(s: &str, max: f32) -> Result<f32, String> {
+    let v: f32 = s.parse().map_err(|_| format!("invalid number {s:?}"))?;
+    if !(0.0..=max).contains(&v) {
+        return Err(format!("{v} is outside 0.0-{max}"));
+    }
+    Ok(v)
+}
+
+/// Parses `OFFSET:COLOR`, e.g. `0.5:#FF8800`.
+fn parse_stop(s: &str) -> Result<GradientStop, String> {
+    let (offset, color) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected OFFSET:COLOR, got {s:?}"))?;
+    let offset: f32 = offset
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid stop offset {offset:?}"))?;
+    if !(0.0..=1.0).contains(&offset) {
+        return Err(format!("stop offset {offset} is outside 0.0-1.0"));
+    }
+    let color = color.trim().parse().map_err(|e| format!("{e}"))?;
+    Ok(GradientStop::new(offset, color))
+}
+
+/// Turns `\n` into a line break and `\\` into a backslash; any other
+/// backslash is kept as it is.
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Parses `on`/`off` (also `1`/`0`, `true`/`false`).
+fn parse_switch(s: &str) -> Result<bool, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "on" | "1" | "true" => Ok(true),
+        "off" | "0" | "false" => Ok(false),
+        _ => Err(format!("expected on or off, got {s:?}")),
+    }
+}
+
+fn parse_sizes(s: &str) -> Result<Vec<u32>, String> {
+    let sizes = s
+        .split(',')
+        .map(|part| {
+            let part = part.trim();
+            match part.parse::<u32>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(format!("invalid size {part:?}")),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if sizes.is_empty() {
+        return Err("no sizes given".to_string());
+    }
+    Ok(sizes)
+}
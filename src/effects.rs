@@ -0,0 +1,358 @@
+//! Layer effects: shadows, glows, bevels and extrusion made from the
+//! silhouette of the text.
+//!
+//! All lengths in an [`Effect`], [`Bevel`] or [`Extrusion`] are fractions of the icon size and are turned
+//! into pixels here, so a 16px entry gets the same look as the 256px one.
+
+use crate::color;
+use crate::spec::{Bevel, BevelStyle, Blending, Color, Effect, EffectKind, Extrusion};
+use image::RgbaImage;
+
+/// Coverage (0.0..=1.0) per pixel of the icon.
+#[derive(Clone)]
+pub(crate) struct Mask {
+    width: u32,
+    height: u32,
+    data: Vec<f32>,
+}
+
+impl Mask {
+    fn filled(width: u32, height: u32, value: f32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![value; (width * height) as usize],
+        }
+    }
+
+    /// Union of the alpha of `layers`, each placed with its top-left corner
+    /// at `origin`, on a `size`×`size` canvas.
+    pub(crate) fn from_layers(size: u32, layers: &[&RgbaImage], origin: (i32, i32)) -> Self {
+        let mut mask = Self::filled(size, size, 0.0);
+        for layer in layers {
+            for (lx, ly, p) in layer.enumerate_pixels() {
+                let (x, y) = (origin.0 + lx as i32, origin.1 + ly as i32);
+                if let Some(i) = mask.index(x, y) {
+                    mask.data[i] = mask.data[i].max(p[3] as f32 / 255.0);
+                }
+            }
+        }
+        mask
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        (x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32)
+            .then(|| (y as u32 * self.width + x as u32) as usize)
+    }
+
+    fn get(&self, x: i32, y: i32, outside: f32) -> f32 {
+        self.index(x, y).map_or(outside, |i| self.data[i])
+    }
+
+    fn map(mut self, f: impl Fn(f32) -> f32) -> Self {
+        self.data.iter_mut().for_each(|v| *v = f(*v));
+        self
+    }
+
+    /// The mask moved by (`dx`, `dy`) pixels, bilinearly for fractional
+    /// shifts; pixels moved in from outside get `outside`.
+    fn shifted(&self, dx: f32, dy: f32, outside: f32) -> Self {
+        let (ix, iy) = (dx.floor(), dy.floor());
+        let (fx, fy) = (dx - ix, dy - iy);
+        let (ix, iy) = (ix as i32, iy as i32);
+        let mut out = Self::filled(self.width, self.height, 0.0);
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let (sx, sy) = (x - ix, y - iy);
+                let row =
+                    self.get(sx, sy, outside) * (1.0 - fx) + self.get(sx - 1, sy, outside) * fx;
+                let above = self.get(sx, sy - 1, outside) * (1.0 - fx)
+                    + self.get(sx - 1, sy - 1, outside) * fx;
+                out.data[(y as u32 * self.width + x as u32) as usize] =
+                    row * (1.0 - fy) + above * fy;
+            }
+        }
+        out
+    }
+
+    /// Grow the shape by `radius` pixels with a round brush.
+    fn dilated(&self, radius: f32) -> Self {
+        if radius < 0.05 {
+            return self.clone();
+        }
+        // 縁からの距離で塗るので、半径が大きくても一度の距離変換で済む
+        let data = self
+            .signed_distance()
+            .into_iter()
+            .map(|d| (d + radius + 0.5).clamp(0.0, 1.0))
+            .collect();
+        Self {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+
+    /// Gaussian blur with blur radius `radius` pixels (σ = radius / 2, as in
+    /// CSS). Edge pixels are extended past the border.
+    fn blurred(&self, radius: f32) -> Self {
+        let sigma = radius / 2.0;
+        if sigma < 0.1 {
+            return self.clone();
+        }
+        let reach = (sigma * 3.0).ceil() as i32;
+        let mut kernel: Vec<f32> = (-reach..=reach)
+            .map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp())
+            .collect();
+        let sum: f32 = kernel.iter().sum();
+        kernel.iter_mut().for_each(|k| *k /= sum);
+
+        let (w, h) = (self.width as i32, self.height as i32);
+        let pass = |src: &Mask, horizontal: bool| {
+            let mut out = Mask::filled(src.width, src.height, 0.0);
+            for y in 0..h {
+                for x in 0..w {
+                    let v: f32 = kernel
+                        .iter()
+                        .zip(-reach..=reach)
+                        .map(|(k, i)| {
+                            let (sx, sy) = if horizontal {
+                                ((x + i).clamp(0, w - 1), y)
+                            } else {
+                                (x, (y + i).clamp(0, h - 1))
+                            };
+                            k * src.data[(sy * w + sx) as usize]
+                        })
+                        .sum();
+                    out.data[(y * w + x) as usize] = v;
+                }
+            }
+            out
+        };
+        pass(&pass(self, true), false)
+    }
+
+    /// Distance in pixels from each pixel to the outline of the shape,
+    /// positive inside. Edge pixels use their coverage for sub-pixel accuracy.
+    fn signed_distance(&self) -> Vec<f32> {
+        let inside = |i: usize| self.data[i] >= 0.5;
+        let to_outside = squared_distances(self.width, self.height, |i| !inside(i));
+        let to_inside = squared_distances(self.width, self.height, inside);
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| {
+                if c > 0.0 && c < 1.0 {
+                    c - 0.5
+                } else if inside(i) {
+                    to_outside[i].sqrt() - 0.5
+                } else {
+                    0.5 - to_inside[i].sqrt()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Squared Euclidean distance from each pixel to the nearest pixel where
+/// `feature` holds (Felzenszwalb & Huttenlocher), very large if there is none.
+fn squared_distances(width: u32, height: u32, feature: impl Fn(usize) -> bool) -> Vec<f32> {
+    const FAR: f32 = 1e20;
+    let (w, h) = (width as usize, height as usize);
+    let mut dist: Vec<f32> = (0..w * h)
+        .map(|i| if feature(i) { 0.0 } else { FAR })
+        .collect();
+    let mut line = Vec::new();
+    let mut out = vec![0.0; w.max(h)];
+    for x in 0..w {
+        line.clear();
+        line.extend((0..h).map(|y| dist[y * w + x]));
+        distance_1d(&line, &mut out[..h]);
+        for y in 0..h {
+            dist[y * w + x] = out[y];
+        }
+    }
+    for y in 0..h {
+        line.clear();
+        line.extend_from_slice(&dist[y * w..(y + 1) * w]);
+        distance_1d(&line, &mut out[..w]);
+        dist[y * w..(y + 1) * w].copy_from_slice(&out[..w]);
+    }
+    dist
+}
+
+/// One-dimensional squared distance transform of the sampled function `f`:
+/// the lower envelope of the parabolas rooted at each sample.
+fn distance_1d(f: &[f32], out: &mut [f32]) {
+    let n = f.len();
+    if n == 0 {
+        return;
+    }
+    let mut roots = vec![0usize; n];
+    let mut bounds = vec![0.0f32; n + 1];
+    let mut k = 0;
+    bounds[0] = f32::NEG_INFINITY;
+    bounds[1] = f32::INFINITY;
+    let sq = |q: usize| (q * q) as f32;
+    for q in 1..n {
+        loop {
+            let p = roots[k];
+            let s = ((f[q] + sq(q)) - (f[p] + sq(p))) / (2.0 * (q - p) as f32);
+            if s <= bounds[k] {
+                k -= 1;
+                continue;
+            }
+            k += 1;
+            roots[k] = q;
+            bounds[k] = s;
+            bounds[k + 1] = f32::INFINITY;
+            break;
+        }
+    }
+    k = 0;
+    for (q, o) in out.iter_mut().enumerate() {
+        while bounds[k + 1] < q as f32 {
+            k += 1;
+        }
+        let p = roots[k];
+        *o = sq(q.abs_diff(p)) + f[p];
+    }
+}
+
+/// Coverage of `effect` on a `size`×`size` icon whose text has the shape
+/// `silhouette`.
+pub(crate) fn effect_mask(effect: &Effect, silhouette: &Mask, size: u32) -> Mask {
+    let px = |f: f32| f * size as f32;
+    let [dx, dy] = effect
+        .offset
+        .map(|v| px(v.clamp(-Effect::MAX_OFFSET, Effect::MAX_OFFSET)));
+    let spread = px(effect.spread.clamp(0.0, Effect::MAX_SPREAD));
+    let blur = px(effect.blur.clamp(0.0, Effect::MAX_BLUR));
+    match effect.kind {
+        EffectKind::DropShadow | EffectKind::OuterGlow => silhouette
+            .shifted(dx, dy, 0.0)
+            .dilated(spread)
+            .blurred(blur),
+        EffectKind::InnerShadow => {
+            // 文字の外側を影として内側へずらし、文字の形で切り抜く
+            let outside = silhouette.clone().map(|v| 1.0 - v);
+            let mut shadow = outside.shifted(dx, dy, 1.0).dilated(spread).blurred(blur);
+            for (s, v) in shadow.data.iter_mut().zip(&silhouette.data) {
+                *s *= v;
+            }
+            shadow
+        }
+        EffectKind::LongShadow => {
+            let steps = dx.hypot(dy).ceil() as u32;
+            let mut shadow = silhouette.clone();
+            for step in 1..=steps {
+                let t = step as f32 / steps as f32;
+                let copy = silhouette.shifted(dx * t, dy * t, 0.0);
+                for (s, c) in shadow.data.iter_mut().zip(copy.data) {
+                    *s = s.max(c);
+                }
+            }
+            shadow.dilated(spread).blurred(blur)
+        }
+    }
+}
+
+/// Paint `effect`'s colour through `mask` onto `img`.
+pub(crate) fn draw(img: &mut RgbaImage, mask: &Mask, effect: &Effect, blending: Blending) {
+    paint(img, mask, effect.color, effect.opacity, blending);
+}
+
+/// Light a bevel of the text with the shape `silhouette` on a
+/// `size`×`size` icon.
+pub(crate) fn bevel(
+    img: &mut RgbaImage,
+    silhouette: &Mask,
+    bevel: &Bevel,
+    size: u32,
+    blending: Blending,
+) {
+    let width = (bevel.size * size as f32).max(f32::EPSILON);
+    // 縁からの距離を丸みのある断面の高さ (0..=1) に変える
+    let heights = silhouette
+        .signed_distance()
+        .into_iter()
+        .map(|d| {
+            let t = match bevel.style {
+                BevelStyle::Inner => d / width,
+                BevelStyle::Emboss => (d + width) / (2.0 * width),
+            };
+            let t = t.clamp(0.0, 1.0);
+            1.0 - (1.0 - t) * (1.0 - t)
+        })
+        .collect();
+    // 距離場の画素の段差がシェーディングに縞として出ないよう、少しだけ常にぼかす
+    let height = Mask {
+        width: silhouette.width,
+        height: silhouette.height,
+        data: heights,
+    }
+    .blurred(bevel.soften * size as f32 + width.min(1.5));
+
+    // 光は高度 30° から当てる。平らな面は明るさが変わらない
+    let (sin, cos) = bevel.angle.to_radians().sin_cos();
+    let altitude = 30f32.to_radians();
+    let light = (sin * altitude.cos(), -cos * altitude.cos(), altitude.sin());
+    let depth = bevel.depth.clamp(0.0, 1.0);
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    for y in 0..h {
+        for x in 0..w {
+            let at = |x: i32, y: i32| height.get(x.clamp(0, w - 1), y.clamp(0, h - 1), 0.0);
+            let gx = (at(x + 1, y) - at(x - 1, y)) / 2.0 * width;
+            let gy = (at(x, y + 1) - at(x, y - 1)) / 2.0 * width;
+            let shade = (light.2 - gx * light.0 - gy * light.1) / (gx * gx + gy * gy + 1.0).sqrt()
+                - light.2;
+            let (color, strength) = if shade > 0.0 {
+                (bevel.highlight, shade / (1.0 - light.2))
+            } else {
+                (bevel.shadow, -shade / light.2)
+            };
+            let coverage = match bevel.style {
+                BevelStyle::Inner => silhouette.get(x, y, 0.0),
+                BevelStyle::Emboss => 1.0,
+            };
+            let alpha = (strength * depth).min(1.0) * coverage * color.a as f32 / 255.0;
+            if alpha > 0.0 {
+                let pixel = img.get_pixel_mut(x as u32, y as u32);
+                *pixel = color::blend(*pixel, color, alpha, blending);
+            }
+        }
+    }
+}
+
+/// Draw the sides of an extrusion of the text with the shape `silhouette`:
+/// copies stacked from the far end forwards, darker further back.
+pub(crate) fn extrude(
+    img: &mut RgbaImage,
+    silhouette: &Mask,
+    extrusion: &Extrusion,
+    size: u32,
+    blending: Blending,
+) {
+    let length = extrusion.depth * size as f32;
+    let (sin, cos) = extrusion.angle.to_radians().sin_cos();
+    let steps = length.ceil().max(1.0) as u32;
+    let shade = extrusion.shade.clamp(0.0, 1.0);
+    for step in (1..=steps).rev() {
+        let t = step as f32 / steps as f32;
+        let copy = silhouette.shifted(sin * length * t, -cos * length * t, 0.0);
+        let darken = |c: u8| (c as f32 * (1.0 - shade * t)).round() as u8;
+        let c = extrusion.color;
+        let color = Color::rgba(darken(c.r), darken(c.g), darken(c.b), c.a);
+        paint(img, &copy, color, 1.0, blending);
+    }
+}
+
+fn paint(img: &mut RgbaImage, mask: &Mask, color: Color, opacity: f32, blending: Blending) {
+    let opacity = opacity.clamp(0.0, 1.0) * color.a as f32 / 255.0;
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let alpha = mask.get(x as i32, y as i32, 0.0) * opacity;
+        if alpha > 0.0 {
+            *pixel = color::blend(*pixel, color, alpha, blending);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Fills of the text and the background: every [`Fill`] kind becomes a
+//! [`FillShader`] that gives the colour of each pixel it covers.
+
+use crate::color;
+use crate::gradient::Shader;
+use crate::pattern::Tiling;
+use crate::raster::PixelBox;
+use crate::spec::{Color, Fill, GradientSpace, MeshGradient, PatternFill, PatternParams};
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Colour source for the pixels covered by a fill.
+pub trait FillShader {
+    /// Colour at the icon pixel (`x`, `y`). Its alpha scales the coverage
+    /// of the filled shape.
+    fn color_at(&self, x: u32, y: u32) -> Color;
+}
+
+impl FillShader for Color {
+    fn color_at(&self, _x: u32, _y: u32) -> Color {
+        *self
+    }
+}
+
+impl FillShader for Shader {
+    fn color_at(&self, x: u32, y: u32) -> Color {
+        // 画素の中心で測るので、左右や上下が対称になる
+        Shader::color_at(self, x as f32 + 0.5, y as f32 + 0.5)
+    }
+}
+
+/// A [`PatternFill`]: its line colour over its background colour.
+struct PatternShader {
+    tiling: Tiling,
+    color: Color,
+    background: Color,
+}
+
+impl FillShader for PatternShader {
+    fn color_at(&self, x: u32, y: u32) -> Color {
+        let t = self.tiling.coverage(x as f32, y as f32);
+        color::mix(self.background, self.color, t, GradientSpace::Srgb)
+    }
+}
+
+impl PatternShader {
+    fn new(fill: &PatternFill, size: u32) -> Self {
+        let params = PatternParams {
+            density: 1.0 / fill.scale.max(0.1),
+            ..PatternParams::default()
+        };
+        Self {
+            tiling: Tiling::new(fill.pattern, &params, size),
+            color: fill.color,
+            background: fill.background,
+        }
+    }
+}
+
+/// A [`MeshGradient`] stretched over a `size`×`size` icon.
+struct MeshShader {
+    mesh: MeshGradient,
+    size: f32,
+}
+
+impl FillShader for MeshShader {
+    fn color_at(&self, x: u32, y: u32) -> Color {
+        let [top_left, top_right, bottom_right, bottom_left] = self.mesh.corners;
+        let space = self.mesh.space;
+        let u = ((x as f32 + 0.5) / self.size).clamp(0.0, 1.0);
+        let v = ((y as f32 + 0.5) / self.size).clamp(0.0, 1.0);
+        let top = color::mix(top_left, top_right, u, space);
+        let bottom = color::mix(bottom_left, bottom_right, u, space);
+        color::mix(top, bottom, v, space)
+    }
+}
+
+/// An image already scaled for the icon, placed at `origin`.
+struct ImageShader {
+    image: RgbaImage,
+    origin: (i32, i32),
+}
+
+impl FillShader for ImageShader {
+    fn color_at(&self, x: u32, y: u32) -> Color {
+        let (ix, iy) = (x as i32 - self.origin.0, y as i32 - self.origin.1);
+        if ix < 0 || iy < 0 || ix >= self.image.width() as i32 || iy >= self.image.height() as i32 {
+            return Color::rgba(0, 0, 0, 0);
+        }
+        let [r, g, b, a] = self.image.get_pixel(ix as u32, iy as u32).0;
+        Color::rgba(r, g, b, a)
+    }
+}
+
+/// Build the shader for `fill` on a `size`×`size` icon, for a shape that
+/// covers `area` (images are fitted to it). An image that cannot be loaded
+/// fills nothing; call [`load_image`] first to report that as an error.
+pub(crate) fn shader(fill: &Fill, size: u32, area: PixelBox) -> Box<dyn FillShader> {
+    match fill {
+        Fill::Solid { color } => Box::new(*color),
+        Fill::Gradient(gradient) => Box::new(Shader::new(gradient, size, size)),
+        Fill::Pattern(pattern) => Box::new(PatternShader::new(pattern, size)),
+        Fill::Mesh(mesh) => Box::new(MeshShader {
+            mesh: mesh.clone(),
+            size: size as f32,
+        }),
+        Fill::Image(image) => match load_image(&image.path) {
+            Ok(source) => Box::new(cover(&source, area)),
+            Err(_) => Box::new(Color::rgba(0, 0, 0, 0)),
+        },
+    }
+}
+
+/// Scale `source` to cover `area`, centred, keeping its aspect ratio.
+fn cover(source: &RgbaImage, area: PixelBox) -> ImageShader {
+    let (sw, sh) = (source.width().max(1) as f32, source.height().max(1) as f32);
+    let scale = (area.width as f32 / sw).max(area.height as f32 / sh);
+    let (w, h) = ((sw * scale).ceil() as u32, (sh * scale).ceil() as u32);
+    ImageShader {
+        image: imageops::resize(source, w.max(1), h.max(1), FilterType::CatmullRom),
+        origin: (
+            area.x - (w as i32 - area.width as i32) / 2,
+            area.y - (h as i32 - area.height as i32) / 2,
+        ),
+    }
+}
+
+/// Load an image file for an image fill. Images are cached by path; a file
+/// that changed on disk since it was cached is read again and replaces the
+/// old entry.
+pub fn load_image(path: &Path) -> Result<Arc<RgbaImage>, image::ImageError> {
+    type Entry = (Option<SystemTime>, Arc<RgbaImage>);
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, Entry>>> = OnceLock::new();
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some((cached, image)) = cache.lock().unwrap().get(path) {
+        if *cached == modified {
+            return Ok(image.clone());
+        }
+    }
+    let image = Arc::new(image::open(path)?.to_rgba8());
+    cache
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), (modified, image.clone()));
+    Ok(image)
+}
@@ -0,0 +1,148 @@
+//! Fill editor widget: the fill type and the settings of that type.
+
+use crate::gradient_editor::GradientEditor;
+use eframe::egui;
+use icon_marker::{
+    fill, Color, Fill, Gradient, GradientSpace, ImageFill, MeshGradient, Pattern, PatternFill,
+};
+use rfd::FileDialog;
+
+/// Editor state kept between frames.
+#[derive(Default)]
+pub struct FillEditor {
+    gradient: GradientEditor,
+    /// Last settings of the other fill types, restored when switching back.
+    stash: Vec<Fill>,
+}
+
+impl FillEditor {
+    /// Show the editor for `fill`. Returns true when it changed; problems
+    /// loading an image are reported in `status`.
+    pub fn show(&mut self, ui: &mut egui::Ui, fill: &mut Fill, status: &mut String) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label("  Type:");
+            for kind in Fill::KINDS {
+                if ui.selectable_label(fill.kind() == kind, kind).clicked() && fill.kind() != kind {
+                    changed |= self.switch(fill, kind, status);
+                }
+            }
+        });
+
+        match fill {
+            Fill::Solid { color } => {
+                ui.horizontal(|ui| {
+                    ui.label("  Color:");
+                    changed |= crate::color_button(ui, color);
+                });
+            }
+            Fill::Gradient(gradient) => changed |= self.gradient.show(ui, gradient),
+            Fill::Mesh(mesh) => changed |= mesh_editor(ui, mesh),
+            Fill::Pattern(pattern) => changed |= pattern_editor(ui, pattern),
+            Fill::Image(image) => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("  Image: {}", crate::file_name(&image.path)));
+                    if ui.button("Choose image…").clicked() {
+                        if let Some(picked) = pick_image("Choose fill image", status) {
+                            *image = picked;
+                            changed = true;
+                        }
+                    }
+                });
+            }
+        }
+        changed
+    }
+
+    /// Change `fill` to the type `kind`, restoring its previous settings.
+    fn switch(&mut self, fill: &mut Fill, kind: &str, status: &mut String) -> bool {
+        let next = match self.stash.iter().position(|f| f.kind() == kind) {
+            Some(i) => self.stash.remove(i),
+            None => match kind {
+                "solid" => Fill::Solid {
+                    color: Color::rgb(120, 90, 220),
+                },
+                "gradient" => Fill::Gradient(Gradient::default()),
+                "mesh" => Fill::Mesh(MeshGradient::default()),
+                "pattern" => Fill::Pattern(PatternFill::default()),
+                // 画像は選ばれたときだけ切り替える
+                _ => match pick_image("Choose fill image", status) {
+                    Some(image) => Fill::Image(image),
+                    None => return false,
+                },
+            },
+        };
+        self.stash.push(std::mem::replace(fill, next));
+        true
+    }
+}
+
+/// Corner colours laid out as they sit on the icon.
+fn mesh_editor(ui: &mut egui::Ui, mesh: &mut MeshGradient) -> bool {
+    let mut changed = false;
+    let [top_left, top_right, bottom_right, bottom_left] = &mut mesh.corners;
+    ui.horizontal(|ui| {
+        ui.label("  Top:");
+        changed |= crate::color_button(ui, top_left);
+        changed |= crate::color_button(ui, top_right);
+    });
+    ui.horizontal(|ui| {
+        ui.label("  Bottom:");
+        changed |= crate::color_button(ui, bottom_left);
+        changed |= crate::color_button(ui, bottom_right);
+    });
+    ui.horizontal(|ui| {
+        ui.label("  Interpolate:");
+        for space in GradientSpace::ALL {
+            changed |= ui
+                .selectable_value(&mut mesh.space, space, space.name())
+                .changed();
+        }
+    });
+    changed
+}
+
+fn pattern_editor(ui: &mut egui::Ui, pattern: &mut PatternFill) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("  Pattern:");
+        egui::ComboBox::from_id_salt("fill-pattern")
+            .selected_text(pattern.pattern.name())
+            .show_ui(ui, |ui| {
+                for p in Pattern::ALL {
+                    changed |= ui
+                        .selectable_value(&mut pattern.pattern, p, p.name())
+                        .changed();
+                }
+            });
+    });
+    ui.horizontal(|ui| {
+        ui.label("  Lines:");
+        changed |= crate::color_button(ui, &mut pattern.color);
+        ui.label("  Background:");
+        changed |= crate::color_button(ui, &mut pattern.background);
+    });
+    ui.horizontal(|ui| {
+        ui.label("  Scale:");
+        changed |= ui
+            .add(egui::Slider::new(&mut pattern.scale, 0.5..=4.0).fixed_decimals(1))
+            .changed();
+    });
+    changed
+}
+
+/// Ask for an image file and check that it loads; errors go to `status`.
+pub(crate) fn pick_image(title: &str, status: &mut String) -> Option<ImageFill> {
+    let path = FileDialog::new()
+        .set_title(title)
+        .add_filter("Image", &["png", "jpg", "jpeg"])
+        .pick_file()?;
+    match fill::load_image(&path) {
+        Ok(_) => Some(ImageFill { path }),
+        Err(e) => {
+            *status = format!("Error loading image: {e}");
+            None
+        }
+    }
+}
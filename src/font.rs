@@ -0,0 +1,336 @@
+//! Font lookup.
+//!
+//! Fonts are searched for in the platform font directories (see [`font_dirs`]).
+//! The default font is always the copy of Aoboshi One compiled into the binary,
+//! so rendering never fails for lack of a font and looks the same everywhere.
+
+use crate::spec::{FontSpec, IconSpec};
+use ab_glyph::FontVec;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Aoboshi One, shipped in `fonts/` and compiled into the binary.
+pub static BUNDLED_FONT: &[u8] = include_bytes!("../fonts/AoboshiOne-Regular.ttf");
+
+/// Directories are scanned at most this deep; system font trees are shallow
+/// and this keeps a stray symlink loop from hanging the scan.
+const MAX_SCAN_DEPTH: usize = 4;
+
+/// Font directories for the current platform, most specific first.
+pub fn font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    // exe と同じディレクトリの fonts/ (配布用)
+    if let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        dirs.push(exe_dir.join("fonts"));
+    }
+
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from);
+
+    if cfg!(windows) {
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            dirs.push(
+                PathBuf::from(local)
+                    .join("Microsoft")
+                    .join("Windows")
+                    .join("Fonts"),
+            );
+        }
+        let windir = std::env::var_os("WINDIR")
+            .or_else(|| std::env::var_os("SystemRoot"))
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\Windows"));
+        dirs.push(windir.join("Fonts"));
+    } else if cfg!(target_os = "macos") {
+        if let Some(home) = &home {
+            dirs.push(home.join("Library").join("Fonts"));
+        }
+        dirs.push(PathBuf::from("/Library/Fonts"));
+        dirs.push(PathBuf::from("/System/Library/Fonts"));
+    } else {
+        if let Some(data) = std::env::var_os("XDG_DATA_HOME") {
+            dirs.push(PathBuf::from(data).join("fonts"));
+        }
+        if let Some(home) = &home {
+            dirs.push(home.join(".local").join("share").join("fonts"));
+            dirs.push(home.join(".fonts"));
+        }
+        dirs.push(PathBuf::from("/usr/local/share/fonts"));
+        dirs.push(PathBuf::from("/usr/share/fonts"));
+    }
+
+    dirs
+}
+
+/// Every font file (`.ttf`, `.otf`, `.ttc`, `.otc`) under the platform font directories.
+pub fn font_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in font_dirs() {
+        collect_font_files(&dir, MAX_SCAN_DEPTH, &mut files);
+    }
+    files
+}
+
+fn collect_font_files(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                collect_font_files(&path, depth - 1, out);
+            }
+        } else if is_font_file(&path) {
+            out.push(path);
+        }
+    }
+}
+
+fn is_font_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ["ttf", "otf", "ttc", "otc"].contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// A parsed font face. The face index is kept so the shaper can open the
+/// same face of a collection from the raw data.
+pub struct LoadedFont {
+    pub font: FontVec,
+    pub index: u32,
+}
+
+impl LoadedFont {
+    fn from_vec(data: Vec<u8>, index: u32) -> Option<Self> {
+        let font = FontVec::try_from_vec_and_index(data, index).ok()?;
+        Some(Self { font, index })
+    }
+
+    /// The raw font file this face was loaded from.
+    pub fn data(&self) -> &[u8] {
+        self.font.as_slice()
+    }
+}
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(PathBuf, std::io::Error),
+    /// The file is not a font `ab_glyph` can read.
+    Invalid(PathBuf),
+    /// The file has fewer faces than the requested index.
+    NoFace(PathBuf, u32),
+    /// No installed font matches the requested family and style.
+    NotFound(FontSpec),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            FontError::Invalid(path) => write!(f, "{}: not a usable font file", path.display()),
+            FontError::NoFace(path, index) => {
+                write!(f, "{}: font has no face #{index}", path.display())
+            }
+            FontError::NotFound(spec) => write!(f, "font not found: {spec}"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+/// One face of a font file. Collections (`.ttc`/`.otc`) hold several faces,
+/// told apart by `index`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontFace {
+    pub family: String,
+    pub style: String,
+    pub path: PathBuf,
+    pub index: u32,
+}
+
+impl FontFace {
+    pub fn to_spec(&self) -> FontSpec {
+        FontSpec::family(&self.family, Some(&self.style))
+    }
+}
+
+/// Name table IDs; the typographic names group more than four styles per family.
+const NAME_FAMILY: u16 = 1;
+const NAME_SUBFAMILY: u16 = 2;
+const NAME_TYPOGRAPHIC_FAMILY: u16 = 16;
+const NAME_TYPOGRAPHIC_SUBFAMILY: u16 = 17;
+
+/// Look up a name, preferring English (US) over other languages.
+fn face_name(face: &ttf_parser::Face, ids: &[u16]) -> Option<String> {
+    for &id in ids {
+        let mut found = None;
+        for name in face.names().into_iter().filter(|n| n.name_id == id) {
+            let Some(value) = name.to_string() else {
+                continue;
+            };
+            if name.language() == ttf_parser::Language::English_UnitedStates {
+                return Some(value);
+            }
+            found.get_or_insert(value);
+        }
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+/// Read the family and style names of the face at `index` in `data`.
+fn read_face(data: &[u8], index: u32, path: &Path) -> Option<FontFace> {
+    let face = ttf_parser::Face::parse(data, index).ok()?;
+    let family = face_name(&face, &[NAME_TYPOGRAPHIC_FAMILY, NAME_FAMILY])?;
+    let style = face_name(&face, &[NAME_TYPOGRAPHIC_SUBFAMILY, NAME_SUBFAMILY])
+        .unwrap_or_else(|| "Regular".to_string());
+    Some(FontFace {
+        family,
+        style,
+        path: path.to_path_buf(),
+        index,
+    })
+}
+
+/// Every face in `data`: one for a plain font, all members of a collection.
+fn read_faces(data: &[u8], path: &Path) -> Vec<FontFace> {
+    let count = ttf_parser::fonts_in_collection(data).unwrap_or(1);
+    (0..count)
+        .filter_map(|index| read_face(data, index, path))
+        .collect()
+}
+
+/// List the faces in the font file at `path` with their family/style names.
+pub fn faces_in_file(path: &Path) -> Result<Vec<FontFace>, FontError> {
+    let data = std::fs::read(path).map_err(|e| FontError::Io(path.to_path_buf(), e))?;
+    let faces = read_faces(&data, path);
+    if faces.is_empty() {
+        return Err(FontError::Invalid(path.to_path_buf()));
+    }
+    Ok(faces)
+}
+
+/// Faces of every installed font, sorted by family and style.
+/// The directories are scanned once per process.
+pub fn system_faces() -> &'static [FontFace] {
+    static FACES: OnceLock<Vec<FontFace>> = OnceLock::new();
+    FACES.get_or_init(|| {
+        let mut faces: Vec<FontFace> = font_files()
+            .iter()
+            .filter_map(|path| std::fs::read(path).ok().map(|data| read_faces(&data, path)))
+            .flatten()
+            .collect();
+        faces.sort_by(|a, b| {
+            (a.family.to_lowercase(), &a.style).cmp(&(b.family.to_lowercase(), &b.style))
+        });
+        faces.dedup_by(|a, b| a.family == b.family && a.style == b.style);
+        faces
+    })
+}
+
+/// Distinct family names among [`system_faces`].
+pub fn system_families() -> Vec<&'static str> {
+    let mut families: Vec<&str> = system_faces().iter().map(|f| f.family.as_str()).collect();
+    families.dedup();
+    families
+}
+
+/// Installed faces belonging to `family` (case-insensitive).
+pub fn faces_of(family: &str) -> impl Iterator<Item = &'static FontFace> + '_ {
+    system_faces()
+        .iter()
+        .filter(move |f| f.family.eq_ignore_ascii_case(family))
+}
+
+/// Style names foundries use for the upright, normal-weight face.
+const REGULAR_STYLES: [&str; 4] = ["Regular", "Book", "Normal", "Roman"];
+
+/// Pick the face for `family`/`style`; without a style the regular face wins.
+fn find_face(family: &str, style: Option<&str>) -> Option<&'static FontFace> {
+    match style {
+        Some(style) => faces_of(family).find(|f| f.style.eq_ignore_ascii_case(style)),
+        None => faces_of(family)
+            .find(|f| {
+                REGULAR_STYLES
+                    .iter()
+                    .any(|r| f.style.eq_ignore_ascii_case(r))
+            })
+            .or_else(|| faces_of(family).next()),
+    }
+}
+
+fn load_spec(spec: &FontSpec) -> Result<LoadedFont, FontError> {
+    let (path, index) = match (&spec.path, &spec.family) {
+        (Some(path), _) => (path.clone(), spec.index),
+        (None, Some(family)) => find_face(family, spec.style.as_deref())
+            .map(|face| (face.path.clone(), face.index))
+            .ok_or_else(|| FontError::NotFound(spec.clone()))?,
+        (None, None) => return Ok(bundled_font()),
+    };
+    let data = std::fs::read(&path).map_err(|e| FontError::Io(path.clone(), e))?;
+    if index >= ttf_parser::fonts_in_collection(&data).unwrap_or(1) {
+        return Err(FontError::NoFace(path, index));
+    }
+    LoadedFont::from_vec(data, index).ok_or(FontError::Invalid(path))
+}
+
+/// Load the font described by `spec`. Fonts are cached, so repeated calls
+/// (one per preview update or ICO size) do not touch the disk again.
+pub fn resolve(spec: &FontSpec) -> Result<Arc<LoadedFont>, FontError> {
+    if spec.is_bundled() {
+        return Ok(default_font());
+    }
+    static CACHE: OnceLock<Mutex<HashMap<FontSpec, Arc<LoadedFont>>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(font) = cache.lock().unwrap().get(spec) {
+        return Ok(font.clone());
+    }
+    let font = Arc::new(load_spec(spec)?);
+    cache.lock().unwrap().insert(spec.clone(), font.clone());
+    Ok(font)
+}
+
+/// The compiled-in Aoboshi One.
+pub fn bundled_font() -> LoadedFont {
+    LoadedFont::from_vec(BUNDLED_FONT.to_vec(), 0).expect("bundled font is a valid TrueType file")
+}
+
+/// Load `spec.font` followed by `spec.fallback_fonts`, ending with the
+/// bundled font so its Japanese coverage backs up any other choice.
+pub fn resolve_chain(spec: &IconSpec) -> Result<Vec<Arc<LoadedFont>>, FontError> {
+    let mut chain = vec![resolve(&spec.font)?];
+    for fallback in &spec.fallback_fonts {
+        chain.push(resolve(fallback)?);
+    }
+    if !spec.font.is_bundled() && !spec.fallback_fonts.iter().any(FontSpec::is_bundled) {
+        chain.push(default_font());
+    }
+    Ok(chain)
+}
+
+/// Like [`resolve_chain`], but fonts that fail to load are skipped (the
+/// default font replaces an unusable primary) instead of failing.
+pub fn load_chain(spec: &IconSpec) -> Vec<Arc<LoadedFont>> {
+    let mut chain = vec![resolve(&spec.font).unwrap_or_else(|_| default_font())];
+    chain.extend(spec.fallback_fonts.iter().filter_map(|f| resolve(f).ok()));
+    if !spec.font.is_bundled() && !spec.fallback_fonts.iter().any(FontSpec::is_bundled) {
+        chain.push(default_font());
+    }
+    chain
+}
+
+/// The font used when the spec does not name one: always the compiled-in
+/// Aoboshi One, never an installed copy of a possibly different version.
+pub fn default_font() -> Arc<LoadedFont> {
+    static FONT: OnceLock<Arc<LoadedFont>> = OnceLock::new();
+    FONT.get_or_init(|| Arc::new(bundled_font())).clone()
+}
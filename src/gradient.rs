@@ -0,0 +1,97 @@
+//! Evaluating a [`Gradient`] at pixel positions.
+
+use crate::color;
+use crate::spec::{Color, Gradient, GradientKind, GradientSpace, GradientStop};
+use std::f32::consts::TAU;
+
+/// A [`Gradient`] prepared for one canvas size: stops sorted, direction
+/// precomputed, so that [`Shader::color_at`] is cheap per pixel.
+pub struct Shader {
+    kind: GradientKind,
+    space: GradientSpace,
+    stops: Vec<GradientStop>,
+    center: (f32, f32),
+    /// Linear: unit direction divided by the gradient line's length.
+    dir: (f32, f32),
+    /// Radial: distance from the centre to the farthest corner.
+    radius: f32,
+    /// Conic: start angle in radians, clockwise from the top.
+    start: f32,
+}
+
+impl Shader {
+    pub fn new(gradient: &Gradient, width: u32, height: u32) -> Self {
+        let (w, h) = (width as f32, height as f32);
+        let mut stops = gradient.stops.clone();
+        stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+        let angle = gradient.angle.to_radians();
+        let (sin, cos) = angle.sin_cos();
+        // CSS と同じく、グラデーション線の長さは角を通るように取る
+        let length = (w * sin).abs() + (h * cos).abs();
+        Self {
+            kind: gradient.kind,
+            space: gradient.space,
+            stops,
+            center: (w / 2.0, h / 2.0),
+            dir: (
+                sin / length.max(f32::EPSILON),
+                -cos / length.max(f32::EPSILON),
+            ),
+            radius: (w * w + h * h).sqrt() / 2.0,
+            start: angle,
+        }
+    }
+
+    /// Position along the gradient (0.0..=1.0) of the point (`x`, `y`).
+    pub fn offset_at(&self, x: f32, y: f32) -> f32 {
+        let (dx, dy) = (x - self.center.0, y - self.center.1);
+        let t = match self.kind {
+            GradientKind::Linear => dx * self.dir.0 + dy * self.dir.1 + 0.5,
+            GradientKind::Radial => (dx * dx + dy * dy).sqrt() / self.radius.max(f32::EPSILON),
+            GradientKind::Conic => {
+                // 上向きを 0 とした時計回りの角度
+                let a = dx.atan2(-dy) - self.start;
+                a.rem_euclid(TAU) / TAU
+            }
+        };
+        t.clamp(0.0, 1.0)
+    }
+
+    /// Colour of the point (`x`, `y`).
+    pub fn color_at(&self, x: f32, y: f32) -> Color {
+        self.color_at_offset(self.offset_at(x, y))
+    }
+
+    /// Colour at position `t` along the gradient.
+    pub fn color_at_offset(&self, t: f32) -> Color {
+        color_at(&self.stops, self.space, t)
+    }
+}
+
+/// Colour at `t` for stops sorted by offset.
+fn color_at(stops: &[GradientStop], space: GradientSpace, t: f32) -> Color {
+    let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+        return Color::rgba(0, 0, 0, 0);
+    };
+    if t <= first.offset {
+        return first.color;
+    }
+    if t >= last.offset {
+        return last.color;
+    }
+    let i = stops.partition_point(|s| s.offset <= t);
+    let (a, b) = (stops[i - 1], stops[i]);
+    let span = b.offset - a.offset;
+    if span <= f32::EPSILON {
+        return b.color;
+    }
+    color::mix(a.color, b.color, (t - a.offset) / span, space)
+}
+
+/// Colour of `gradient` at `t`, for previews; see [`Shader`] for per-pixel use.
+pub fn sample(gradient: &Gradient, t: f32) -> Color {
+    let mut stops = gradient.stops.clone();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+    color_at(&stops, gradient.space, t)
+}
@@ -0,0 +1,170 @@
+//! Gradient editor widget: type, angle, colour space and a bar of colour stops.
+
+use eframe::egui;
+use egui::{pos2, vec2, Color32, Mesh, Rect, Sense, Shape, Stroke};
+use icon_marker::gradient::sample;
+use icon_marker::{Color, Gradient, GradientKind, GradientSpace, GradientStop};
+
+const BAR_HEIGHT: f32 = 18.0;
+const HANDLE_SIZE: f32 = 9.0;
+/// Colour samples across the preview bar.
+const BAR_STEPS: usize = 64;
+
+fn to_color32(c: Color) -> Color32 {
+    Color32::from_rgba_unmultiplied(c.r, c.g, c.b, c.a)
+}
+
+/// Editor state kept between frames.
+#[derive(Default)]
+pub struct GradientEditor {
+    /// Index into `Gradient::stops` of the stop being edited.
+    selected: usize,
+}
+
+impl GradientEditor {
+    /// Show the editor for `gradient`. Returns true when it changed.
+    ///
+    /// Click the bar to add a stop, drag a handle to move it.
+    pub fn show(&mut self, ui: &mut egui::Ui, gradient: &mut Gradient) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label("  Type:");
+            for kind in GradientKind::ALL {
+                changed |= ui
+                    .selectable_value(&mut gradient.kind, kind, kind.name())
+                    .changed();
+            }
+        });
+        if gradient.kind != GradientKind::Radial {
+            ui.horizontal(|ui| {
+                ui.label("  Angle:");
+                changed |= ui
+                    .add(egui::Slider::new(&mut gradient.angle, 0.0..=360.0).suffix("°"))
+                    .changed();
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("  Interpolate:");
+            for space in GradientSpace::ALL {
+                changed |= ui
+                    .selectable_value(&mut gradient.space, space, space.name())
+                    .changed();
+            }
+        });
+
+        changed |= self.stop_bar(ui, gradient);
+
+        if gradient.stops.is_empty() {
+            return changed;
+        }
+        self.selected = self.selected.min(gradient.stops.len() - 1);
+        let can_remove = gradient.stops.len() > 2;
+        ui.horizontal(|ui| {
+            let stop = &mut gradient.stops[self.selected];
+            ui.label("  Stop:");
+            changed |= crate::color_button(ui, &mut stop.color);
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut stop.offset)
+                        .range(0.0..=1.0)
+                        .speed(0.005)
+                        .fixed_decimals(2),
+                )
+                .changed();
+            if ui
+                .add_enabled(can_remove, egui::Button::new("Remove"))
+                .clicked()
+            {
+                gradient.stops.remove(self.selected);
+                self.selected = self.selected.saturating_sub(1);
+                changed = true;
+            }
+        });
+        changed
+    }
+
+    /// The preview bar with one draggable handle per stop below it.
+    fn stop_bar(&mut self, ui: &mut egui::Ui, gradient: &mut Gradient) -> bool {
+        let mut changed = false;
+        let width = ui.available_width().min(280.0);
+        let (rect, bar) =
+            ui.allocate_exact_size(vec2(width, BAR_HEIGHT + HANDLE_SIZE + 2.0), Sense::click());
+        let bar_rect = Rect::from_min_size(rect.min, vec2(width, BAR_HEIGHT));
+        let x_of = |offset: f32| bar_rect.left() + offset.clamp(0.0, 1.0) * bar_rect.width();
+        let offset_of = |x: f32| ((x - bar_rect.left()) / bar_rect.width()).clamp(0.0, 1.0);
+
+        // 新しいストップは、クリックした位置の現在の色で追加する
+        if bar.clicked() {
+            if let Some(pos) = bar.interact_pointer_pos().filter(|p| bar_rect.contains(*p)) {
+                let offset = offset_of(pos.x);
+                gradient
+                    .stops
+                    .push(GradientStop::new(offset, sample(gradient, offset)));
+                self.selected = gradient.stops.len() - 1;
+                changed = true;
+            }
+        }
+
+        for i in 0..gradient.stops.len() {
+            let x = x_of(gradient.stops[i].offset);
+            let handle = Rect::from_center_size(
+                pos2(x, bar_rect.bottom() + 1.0 + HANDLE_SIZE / 2.0),
+                vec2(HANDLE_SIZE, HANDLE_SIZE),
+            );
+            let response = ui.interact(handle, bar.id.with(i), Sense::click_and_drag());
+            if response.clicked() || response.drag_started() {
+                self.selected = i;
+            }
+            if response.dragged() {
+                let stop = &mut gradient.stops[i];
+                stop.offset = offset_of(x_of(stop.offset) + response.drag_delta().x);
+                changed = true;
+            }
+        }
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter();
+            let mut mesh = Mesh::default();
+            for step in 0..=BAR_STEPS {
+                let t = step as f32 / BAR_STEPS as f32;
+                let color = to_color32(sample(gradient, t));
+                let x = bar_rect.left() + t * bar_rect.width();
+                mesh.colored_vertex(pos2(x, bar_rect.top()), color);
+                mesh.colored_vertex(pos2(x, bar_rect.bottom()), color);
+                if step > 0 {
+                    let i = (step * 2) as u32;
+                    mesh.add_triangle(i - 2, i - 1, i);
+                    mesh.add_triangle(i - 1, i, i + 1);
+                }
+            }
+            painter.add(Shape::mesh(mesh));
+            painter.rect_stroke(
+                bar_rect,
+                0.0,
+                ui.visuals().widgets.noninteractive.bg_stroke,
+                egui::StrokeKind::Inside,
+            );
+
+            for (i, stop) in gradient.stops.iter().enumerate() {
+                let x = x_of(stop.offset);
+                let top = bar_rect.bottom() + 1.0;
+                let stroke = if i == self.selected {
+                    Stroke::new(2.0, ui.visuals().selection.stroke.color)
+                } else {
+                    ui.visuals().widgets.inactive.fg_stroke
+                };
+                painter.add(Shape::convex_polygon(
+                    vec![
+                        pos2(x, top),
+                        pos2(x + HANDLE_SIZE / 2.0, top + HANDLE_SIZE),
+                        pos2(x - HANDLE_SIZE / 2.0, top + HANDLE_SIZE),
+                    ],
+                    to_color32(stop.color),
+                    stroke,
+                ));
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,70 @@
+//! ICO file writer.
+
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+/// Write an ICO file with multiple sizes.
+/// Each image must already be rendered at the correct size.
+/// All sizes are stored as PNG (Vista+ compatible). This avoids BMP DIB header
+/// issues (biHeight doubling, AND mask requirements) that cause rendering glitches
+/// on Windows, such as the top of the icon being clipped.
+pub fn write_ico(path: &Path, images: &[(u32, RgbaImage)]) -> std::io::Result<()> {
+    struct IcoEntry {
+        width: u8, // 0 means 256
+        height: u8,
+        planes: u16,
+        bpp: u16,
+        data: Vec<u8>,
+    }
+
+    let mut entries = Vec::new();
+
+    for (s, resized) in images {
+        let s = *s;
+
+        // Encode all sizes as PNG — clean, no BMP header pitfalls.
+        let mut buf = Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(resized.clone())
+            .write_to(&mut buf, ImageFormat::Png)
+            .map_err(std::io::Error::other)?;
+        entries.push(IcoEntry {
+            width: if s >= 256 { 0 } else { s as u8 },
+            height: if s >= 256 { 0 } else { s as u8 },
+            planes: 1,
+            bpp: 32,
+            data: buf.into_inner(),
+        });
+    }
+
+    // Write ICO file
+    let mut file = File::create(path)?;
+    let count = entries.len() as u16;
+
+    // ICONDIR header (6 bytes)
+    file.write_all(&0u16.to_le_bytes())?; // idReserved
+    file.write_all(&1u16.to_le_bytes())?; // idType = ICO
+    file.write_all(&count.to_le_bytes())?; // idCount
+
+    // Calculate data offsets
+    let dir_end = 6u32 + 16 * count as u32;
+    let mut offset = dir_end;
+
+    // ICONDIRENTRY for each image (16 bytes each)
+    for entry in &entries {
+        file.write_all(&[entry.width, entry.height, 0, 0])?; // w, h, colorCount, reserved
+        file.write_all(&entry.planes.to_le_bytes())?; // wPlanes
+        file.write_all(&entry.bpp.to_le_bytes())?; // wBitCount
+        file.write_all(&(entry.data.len() as u32).to_le_bytes())?; // dwBytesInRes
+        file.write_all(&offset.to_le_bytes())?; // dwImageOffset
+        offset += entry.data.len() as u32;
+    }
+
+    // Image data
+    for entry in &entries {
+        file.write_all(&entry.data)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+//! The layer stack compositor, and image layers: logos and photos placed on
+//! the icon.
+//!
+//! Every layer is drawn on its own transparent image and composited over
+//! the layers below it with its opacity and [`BlendMode`]. Image layers
+//! resample the original file for every icon size, so a 16×16 entry is not
+//! a blurry copy of the 256×256 one.
+//!
+//! [`BlendMode`]: crate::spec::BlendMode
+
+use crate::color;
+use crate::fill;
+use crate::spec::{Blending, Color, ImageFit, ImageLayer, Layer};
+use image::imageops::{self, FilterType};
+use image::{Rgba, Rgba32FImage, RgbaImage};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// `layer` drawn on a transparent `size`×`size` image, or `None` if the
+/// image cannot be loaded (call [`fill::load_image`] first to report that
+/// as an error) or shrinks to nothing.
+pub(crate) fn image_source(layer: &ImageLayer, size: u32) -> Option<RgbaImage> {
+    let source = fill::load_image(&layer.path).ok()?;
+    let (origin, width, height) = placement(layer, source.dimensions(), size)?;
+    let image = resampled(&layer.path, &source, size, (width, height));
+    let mut img = RgbaImage::new(size, size);
+    imageops::replace(&mut img, image.as_ref(), origin.0.into(), origin.1.into());
+    Some(img)
+}
+
+/// Composite `source`, the content of `layer`, over `img` with the layer's
+/// opacity and blend mode.
+pub(crate) fn composite(
+    img: &mut RgbaImage,
+    source: &RgbaImage,
+    layer: &Layer,
+    blending: Blending,
+) {
+    let opacity = layer.opacity.clamp(0.0, 1.0);
+    for (dst, src) in img.pixels_mut().zip(source.pixels()) {
+        if src[3] == 0 || (layer.clip && dst[3] == 0) {
+            continue;
+        }
+        let paint = color::blend_mode(layer.blend, *dst, Color::rgba(src[0], src[1], src[2], 255));
+        let alpha = src[3] as f32 / 255.0 * opacity;
+        *dst = if layer.clip {
+            // 下のレイヤーの α はそのままで、色だけを混ぜる
+            let [r, g, b, a] = dst.0;
+            let mut out = color::blend(Rgba([r, g, b, 255]), paint, alpha, blending);
+            out[3] = a;
+            out
+        } else {
+            color::blend(*dst, paint, alpha, blending)
+        };
+    }
+}
+
+/// Top-left corner and size of `layer` on a `size`×`size` icon, for a
+/// source image of `dimensions`; `None` when it shrinks to nothing.
+fn placement(
+    layer: &ImageLayer,
+    dimensions: (u32, u32),
+    size: u32,
+) -> Option<((i32, i32), u32, u32)> {
+    let (sw, sh) = (dimensions.0.max(1) as f32, dimensions.1.max(1) as f32);
+    let s = size as f32;
+    let (kx, ky) = match layer.fit {
+        ImageFit::Cover => {
+            let k = (s / sw).max(s / sh);
+            (k, k)
+        }
+        ImageFit::Contain => {
+            let k = (s / sw).min(s / sh);
+            (k, k)
+        }
+        ImageFit::Stretch => (s / sw, s / sh),
+    };
+    let scale = layer.scale.max(0.0);
+    let (w, h) = (sw * kx * scale, sh * ky * scale);
+    let (cx, cy) = (s / 2.0 + layer.offset[0] * s, s / 2.0 + layer.offset[1] * s);
+    let origin = ((cx - w / 2.0).round() as i32, (cy - h / 2.0).round() as i32);
+    let (w, h) = (w.round() as u32, h.round() as u32);
+    (w > 0 && h > 0).then_some((origin, w, h))
+}
+
+/// `source` (loaded from `path`) resampled to `width`×`height` for a
+/// `size`×`size` icon, cached.
+fn resampled(
+    path: &Path,
+    source: &Arc<RgbaImage>,
+    size: u32,
+    (width, height): (u32, u32),
+) -> Arc<RgbaImage> {
+    /// The source image, the size it was resampled to and the result.
+    type Entry = (Arc<RgbaImage>, (u32, u32), Arc<RgbaImage>);
+    static CACHE: OnceLock<Mutex<HashMap<(PathBuf, u32), Entry>>> = OnceLock::new();
+    let key = (path.to_path_buf(), size);
+    let cache = CACHE.get_or_init(Default::default);
+    // 画像やその大きさが変われば、同じアイコンサイズの古い結果を置き換える
+    if let Some((cached_source, dimensions, image)) = cache.lock().unwrap().get(&key) {
+        if Arc::ptr_eq(cached_source, source) && *dimensions == (width, height) {
+            return image.clone();
+        }
+    }
+    let image = Arc::new(resample(source, width, height));
+    cache
+        .lock()
+        .unwrap()
+        .insert(key, (source.clone(), (width, height), image.clone()));
+    image
+}
+
+/// Lanczos resampling in premultiplied linear light: transparent pixels do
+/// not bleed dark fringes into a logo's edges, and fine detail keeps its
+/// brightness when shrunk.
+fn resample(source: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    if source.dimensions() == (width, height) {
+        return source.clone();
+    }
+    let premultiplied = Rgba32FImage::from_fn(source.width(), source.height(), |x, y| {
+        let [r, g, b, a] = source.get_pixel(x, y).0;
+        let a = a as f32 / 255.0;
+        let [r, g, b] = [r, g, b].map(|c| color::srgb_to_linear(c) * a);
+        Rgba([r, g, b, a])
+    });
+    let resized = imageops::resize(&premultiplied, width, height, FilterType::Lanczos3);
+    RgbaImage::from_fn(width, height, |x, y| {
+        let [r, g, b, a] = resized.get_pixel(x, y).0;
+        // Lanczos は縁で負の値やはみ出しを作るので切り詰める
+        let a = a.clamp(0.0, 1.0);
+        if a <= 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let c = |v: f32| color::linear_to_srgb(v / a);
+        Rgba([c(r), c(g), c(b), (a * 255.0).round() as u8])
+    })
+}
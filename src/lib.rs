@@ -0,0 +1,40 @@
+//! IconMarker のレンダリングエンジン。
+//!
+//! GUI・サンプル・ビルドスクリプトから共通で使えるよう、アイコンの描画と
+//! ICO 書き出しをウィンドウに依存しない形で提供する。
+//!
+//! ```no_run
+//! use icon_marker::{render_sizes, write_ico, IconSpec};
+//!
+//! let spec = IconSpec {
+//!     text: "G".to_string(),
+//!     ..IconSpec::default()
+//! };
+//! let images = render_sizes(&spec);
+//! write_ico(std::path::Path::new("icon.ico"), &images).unwrap();
+//! ```
+
+pub mod color;
+mod effects;
+pub mod fill;
+pub mod font;
+pub mod gradient;
+pub mod ico;
+mod layers;
+mod pattern;
+pub mod png_meta;
+pub mod project;
+mod raster;
+pub mod render;
+mod shape;
+pub mod spec;
+mod text;
+
+pub use ico::write_ico;
+pub use render::{render, render_sizes, render_with_fonts};
+pub use spec::{
+    BackgroundShape, Bevel, BevelStyle, BlendMode, Blending, Color, Effect, EffectKind, Extrusion,
+    Fill, FontSpec, Gradient, GradientKind, GradientSpace, GradientStop, IconSpec, ImageFill,
+    ImageFit, ImageLayer, Layer, LayerContent, MeshGradient, ParseColorError, Pattern, PatternFill,
+    PatternParams, Stroke, StrokeAlign, TextAlign, WritingMode,
+};
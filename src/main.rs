@@ -1,103 +1,32 @@
 #![windows_subsystem = "windows"]
 
-use ab_glyph::{FontVec, PxScale};
+mod cli;
+mod fill_editor;
+mod gradient_editor;
+
 use eframe::egui;
 use egui::color_picker::{color_edit_button_srgba, Alpha};
 use egui::{Color32, ColorImage, TextureHandle, TextureOptions};
-use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
-use imageproc::drawing::draw_text_mut;
+use fill_editor::FillEditor;
+use icon_marker::font;
+use icon_marker::spec::is_feature_tag;
+use icon_marker::{
+    png_meta, project, render, render_sizes, write_ico, BackgroundShape, BevelStyle, BlendMode,
+    Blending, Color, Effect, EffectKind, FontSpec, IconSpec, ImageFit, ImageLayer, Layer,
+    LayerContent, Pattern, StrokeAlign, TextAlign, WritingMode,
+};
 use rfd::FileDialog;
-use std::fs::File;
-use std::io::{Cursor, Write};
 use std::path::PathBuf;
-use std::sync::Arc;
-
-fn load_font() -> FontVec {
-    // Aoboshi One を優先的に探す
-    let candidates = [
-        // バンドルフォント (exe と同じディレクトリの fonts/)
-        concat!(env!("CARGO_MANIFEST_DIR"), r"\fonts\AoboshiOne-Regular.ttf"),
-        // システムフォント (ユーザーインストール)
-        r"C:\Users\koya\AppData\Local\Microsoft\Windows\Fonts\AoboshiOne-Regular.ttf",
-        r"C:\Windows\Fonts\AoboshiOne-Regular.ttf",
-        // フォールバック
-        r"C:\Windows\Fonts\meiryob.ttc",
-        r"C:\Windows\Fonts\YuGothB.ttc",
-        r"C:\Windows\Fonts\msgothic.ttc",
-        r"C:\Windows\Fonts\arialbd.ttf",
-        r"C:\Windows\Fonts\arial.ttf",
-    ];
-    for path in &candidates {
-        if let Ok(data) = std::fs::read(path) {
-            if let Ok(font) = FontVec::try_from_vec_and_index(data.clone(), 0) {
-                return font;
-            }
-            if let Ok(font) = FontVec::try_from_vec(data) {
-                return font;
-            }
-        }
-    }
-    panic!("No suitable font found in system fonts");
-}
-
-fn lerp_color(a: Color32, b: Color32, t: f32) -> Rgba<u8> {
-    let t = t.clamp(0.0, 1.0);
-    Rgba([
-        (a.r() as f32 + (b.r() as f32 - a.r() as f32) * t) as u8,
-        (a.g() as f32 + (b.g() as f32 - a.g() as f32) * t) as u8,
-        (a.b() as f32 + (b.b() as f32 - a.b() as f32) * t) as u8,
-        255,
-    ])
-}
 
-fn draw_chevron_pattern(img: &mut RgbaImage, _base: Color32, size: u32) {
-    let width = size as i32;
-    let height = size as i32;
-
-    let spacing = 6; // 線の間隔
-    let zigzag_period = 20; // ジグザグ1周期の幅（px）
-
-    for y in 0..height {
-        for x in 0..width {
-            // 三角波（繰り返しジグザグ）で複数のV字折り返しを作る
-            let half = zigzag_period / 2;
-            let zigzag = ((x % zigzag_period) - half).abs();
-            let pattern_y = (y + zigzag).rem_euclid(spacing);
-
-            let bg = *img.get_pixel(x as u32, y as u32);
-
-            if pattern_y == 0 {
-                // 微かなハイライト線
-                img.put_pixel(
-                    x as u32,
-                    y as u32,
-                    Rgba([
-                        bg[0].saturating_add(10),
-                        bg[1].saturating_add(10),
-                        bg[2].saturating_add(10),
-                        255,
-                    ]),
-                );
-            } else if pattern_y == 1 {
-                // ハイライト直下の微かなシャドウで立体感を出す
-                img.put_pixel(
-                    x as u32,
-                    y as u32,
-                    Rgba([
-                        bg[0].saturating_sub(6),
-                        bg[1].saturating_sub(6),
-                        bg[2].saturating_sub(6),
-                        255,
-                    ]),
-                );
-            }
-        }
+fn main() -> eframe::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "render") {
+        attach_parent_console();
+        std::process::exit(cli::main(&args[1..]));
     }
-}
 
-fn main() -> eframe::Result {
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([620.0, 540.0]),
+        viewport: egui::ViewportBuilder::default().with_inner_size([620.0, 640.0]),
         ..Default::default()
     };
     eframe::run_native(
@@ -107,216 +36,853 @@ fn main() -> eframe::Result {
     )
 }
 
+/// GUI サブシステムの exe でも、コンソールから起動された場合は
+/// stdout/stderr をそのコンソールに出せるようにする。
+#[cfg(windows)]
+fn attach_parent_console() {
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // 失敗しても（コンソールが無いだけなので）無視する
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+/// File name of `path` for display, or "(none)".
+fn file_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "(none)".to_string())
+}
+
+/// Colour button bound to a spec [`Color`]. Returns true when the colour changed.
+fn color_button(ui: &mut egui::Ui, color: &mut Color) -> bool {
+    let mut c = Color32::from_rgba_unmultiplied(color.r, color.g, color.b, color.a);
+    let changed = color_edit_button_srgba(ui, &mut c, Alpha::Opaque).changed();
+    if changed {
+        *color = Color::rgba(c.r(), c.g(), c.b(), c.a());
+    }
+    changed
+}
+
 struct IconMarkerApp {
-    text: String,
-    bg_color: Color32,
-    grad_start: Color32,
-    grad_end: Color32,
-    padding: f32, // 0.0..=0.4 — fraction of canvas used as margin
-    chevron_on: bool,
+    spec: IconSpec,
+    /// Faces of the font file in `spec.font.path`, read when the file changes.
+    file_faces: Option<(PathBuf, Vec<font::FontFace>)>,
+    /// Text box for adding a feature tag that is not in the common list.
+    new_feature_tag: String,
+    bg_fill: FillEditor,
+    text_fill: FillEditor,
+    stroke_fill: FillEditor,
     texture: Option<TextureHandle>,
     needs_update: bool,
     status_msg: String,
-    font: Arc<FontVec>,
 }
 
 impl IconMarkerApp {
     fn new() -> Self {
         Self {
-            text: "G".to_string(),
-            bg_color: Color32::from_rgb(242, 220, 198),
-            grad_start: Color32::from_rgb(120, 90, 220),
-            grad_end: Color32::from_rgb(20, 170, 130),
-            padding: 0.1,
-            chevron_on: true,
+            spec: IconSpec::default(),
+            file_faces: None,
+            new_feature_tag: String::new(),
+            bg_fill: FillEditor::default(),
+            text_fill: FillEditor::default(),
+            stroke_fill: FillEditor::default(),
             texture: None,
             needs_update: true,
             status_msg: String::new(),
-            font: Arc::new(load_font()),
         }
     }
 
-    /// Measure the actual pixel bounding box of text rendered at a given scale.
-    /// Returns (min_x, min_y, max_x, max_y) of non-transparent pixels, or None.
-    fn measure_text_bbox(
-        font: &FontVec,
-        text: &str,
-        scale: f32,
-        canvas: u32,
-    ) -> Option<(u32, u32, u32, u32)> {
-        let white = Rgba([255u8, 255, 255, 255]);
-        let mut tmp = RgbaImage::from_pixel(canvas, canvas, Rgba([0, 0, 0, 0]));
-        draw_text_mut(&mut tmp, white, 0, 0, PxScale::from(scale), font, text);
-
-        let mut min_x = canvas;
-        let mut min_y = canvas;
-        let mut max_x = 0u32;
-        let mut max_y = 0u32;
-        for y in 0..canvas {
-            for x in 0..canvas {
-                if tmp.get_pixel(x, y)[3] > 0 {
-                    min_x = min_x.min(x);
-                    min_y = min_y.min(y);
-                    max_x = max_x.max(x);
-                    max_y = max_y.max(y);
-                }
+    fn save_png(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .set_title("Save PNG")
+            .add_filter("PNG", &["png"])
+            .set_file_name("icon.png")
+            .save_file()
+        {
+            let path = ensure_extension(path, "png");
+            let img = render(&self.spec, 256);
+            match png_meta::save_png(&path, &img, &self.spec) {
+                Ok(_) => self.status_msg = format!("PNG saved: {}", path.display()),
+                Err(e) => self.status_msg = format!("Error: {e}"),
             }
         }
-        if max_x >= min_x && max_y >= min_y {
-            Some((min_x, min_y, max_x, max_y))
-        } else {
-            None
-        }
     }
 
-    fn generate_image(&self, size: u32) -> RgbaImage {
-        let bg = Rgba([self.bg_color.r(), self.bg_color.g(), self.bg_color.b(), 255]);
-        let mut img = RgbaImage::from_pixel(size, size, bg);
-
-        if self.chevron_on {
-            draw_chevron_pattern(&mut img, self.bg_color, size);
-        }
-
-        if self.text.is_empty() {
-            return img;
-        }
-
-        let font = &*self.font;
+    fn save_ico(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .set_title("Save ICO")
+            .add_filter("ICO", &["ico"])
+            .set_file_name("icon.ico")
+            .save_file()
+        {
+            let path = ensure_extension(path, "ico");
+            let images = render_sizes(&self.spec);
 
-        // Step 1: Render at reference scale and measure actual glyph bounding box
-        let ref_scale = 200.0_f32;
-        let ref_canvas = 512u32;
-        let bbox = match Self::measure_text_bbox(font, &self.text, ref_scale, ref_canvas) {
-            Some(b) => b,
-            None => return img,
-        };
-        let ref_w = (bbox.2 - bbox.0 + 1) as f32;
-        let ref_h = (bbox.3 - bbox.1 + 1) as f32;
-
-        // Step 2: Calculate scale to fill the padded inner area
-        let size_i32 = size as i32;
-        let pad_px = ((size as f32) * self.padding).round() as i32;
-        let pad_px = pad_px.clamp(0, size_i32 / 2);
-        let inner_space = (size_i32 - pad_px * 2).max(1);
-        let target = inner_space as f32;
-        let ratio = (target / ref_w).min(target / ref_h);
-        let final_scale = ref_scale * ratio;
-
-        // Step 3: Render at final scale on an oversized canvas to avoid clipping
-        let white = Rgba([255u8, 255, 255, 255]);
-        let tmp_size = size * 2;
-        let mut text_layer = RgbaImage::from_pixel(tmp_size, tmp_size, Rgba([0, 0, 0, 0]));
-        draw_text_mut(
-            &mut text_layer,
-            white,
-            0,
-            0,
-            PxScale::from(final_scale),
-            font,
-            &self.text,
-        );
-
-        // Find actual bounds at final scale
-        let mut min_x = tmp_size;
-        let mut min_y = tmp_size;
-        let mut max_x = 0u32;
-        let mut max_y = 0u32;
-        for y in 0..tmp_size {
-            for x in 0..tmp_size {
-                if text_layer.get_pixel(x, y)[3] > 0 {
-                    min_x = min_x.min(x);
-                    min_y = min_y.min(y);
-                    max_x = max_x.max(x);
-                    max_y = max_y.max(y);
-                }
+            match write_ico(&path, &images) {
+                Ok(_) => self.status_msg = format!("ICO saved: {}", path.display()),
+                Err(e) => self.status_msg = format!("Error writing ICO: {e}"),
             }
         }
+    }
 
-        // Step 4: Crop glyph pixels and target their position within the padded box
-        let glyph_w = max_x - min_x + 1;
-        let glyph_h = max_y - min_y + 1;
+    /// Switch to a loaded spec. Editor state belongs to the old spec (stashed
+    /// fills, the selected gradient stop, the font file's faces), so it is reset.
+    fn replace_spec(&mut self, spec: IconSpec) {
+        self.spec = spec;
+        self.file_faces = None;
+        self.new_feature_tag.clear();
+        self.bg_fill = FillEditor::default();
+        self.text_fill = FillEditor::default();
+        self.stroke_fill = FillEditor::default();
+        self.needs_update = true;
+    }
 
-        let mut glyph_layer = RgbaImage::from_pixel(glyph_w, glyph_h, Rgba([0, 0, 0, 0]));
-        for oy in 0..glyph_h {
-            for ox in 0..glyph_w {
-                let src_pix = text_layer.get_pixel(min_x + ox, min_y + oy);
-                glyph_layer.put_pixel(ox, oy, *src_pix);
+    fn open_project(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .set_title("Open project")
+            .add_filter("IconMarker project", &[project::EXTENSION])
+            .pick_file()
+        {
+            match project::load(&path) {
+                Ok(spec) => {
+                    self.replace_spec(spec);
+                    self.status_msg = format!("Project opened: {}", path.display());
+                }
+                Err(e) => self.status_msg = format!("Error opening project: {e}"),
             }
         }
+    }
 
-        let x_margin = (inner_space - glyph_w as i32).max(0);
-        let y_margin = (inner_space - glyph_h as i32).max(0);
-        let mut target_x = pad_px + (inner_space - glyph_w as i32) / 2;
-        let mut target_y = pad_px + (inner_space - glyph_h as i32) / 2;
-        target_x = target_x.clamp(pad_px, pad_px + x_margin);
-        target_y = target_y.clamp(pad_px, pad_px + y_margin);
-
-        // Step 5: Composite text with gradient onto background, applying offset
-        for y in 0..size {
-            for x in 0..size {
-                let src_x = x as i32 - target_x;
-                let src_y = y as i32 - target_y;
-                if src_x < 0 || src_y < 0 || src_x >= glyph_w as i32 || src_y >= glyph_h as i32 {
-                    continue;
-                }
-                let tp = glyph_layer.get_pixel(src_x as u32, src_y as u32);
-                if tp[3] > 0 {
-                    let t = ((x as f32 + (size as f32 - y as f32)) / (2.0 * size as f32))
-                        .clamp(0.0, 1.0);
-                    let grad = lerp_color(self.grad_start, self.grad_end, t);
-                    let alpha = tp[3] as f32 / 255.0;
-                    let bg_px = img.get_pixel(x, y);
-                    let blended = Rgba([
-                        (grad[0] as f32 * alpha + bg_px[0] as f32 * (1.0 - alpha)) as u8,
-                        (grad[1] as f32 * alpha + bg_px[1] as f32 * (1.0 - alpha)) as u8,
-                        (grad[2] as f32 * alpha + bg_px[2] as f32 * (1.0 - alpha)) as u8,
-                        255,
-                    ]);
-                    img.put_pixel(x, y, blended);
+    fn open_from_png(&mut self) {
+        if let Some(path) = FileDialog::new()
+            .set_title("Open from PNG")
+            .add_filter("PNG", &["png"])
+            .pick_file()
+        {
+            match png_meta::load_spec(&path) {
+                Ok(spec) => {
+                    self.replace_spec(spec);
+                    self.status_msg = format!("Settings restored: {}", path.display());
                 }
+                Err(e) => self.status_msg = format!("Error opening PNG: {e}"),
             }
         }
-
-        img
     }
 
-    fn save_png(&mut self) {
+    fn save_project(&mut self) {
         if let Some(path) = FileDialog::new()
-            .set_title("Save PNG")
-            .add_filter("PNG", &["png"])
-            .set_file_name("icon.png")
+            .set_title("Save project")
+            .add_filter("IconMarker project", &[project::EXTENSION])
+            .set_file_name("icon.toml")
             .save_file()
         {
-            let path = ensure_extension(path, "png");
-            let img = self.generate_image(256);
-            match img.save(&path) {
-                Ok(_) => self.status_msg = format!("PNG saved: {}", path.display()),
-                Err(e) => self.status_msg = format!("Error: {e}"),
+            let path = ensure_extension(path, project::EXTENSION);
+            match project::save(&path, &self.spec) {
+                Ok(_) => self.status_msg = format!("Project saved: {}", path.display()),
+                Err(e) => self.status_msg = format!("Error saving project: {e}"),
             }
         }
     }
 
-    fn save_ico(&mut self) {
+    /// Switch to `font` if it can be loaded; otherwise keep the current one.
+    fn set_font(&mut self, font: FontSpec) {
+        match font::resolve(&font) {
+            Ok(_) => {
+                self.spec.font = font;
+                self.needs_update = true;
+            }
+            Err(e) => self.status_msg = format!("Error loading font: {e}"),
+        }
+    }
+
+    fn load_font_file(&mut self) {
         if let Some(path) = FileDialog::new()
-            .set_title("Save ICO")
-            .add_filter("ICO", &["ico"])
-            .set_file_name("icon.ico")
-            .save_file()
+            .set_title("Load font file")
+            .add_filter("Fonts", &["ttf", "otf", "ttc", "otc"])
+            .pick_file()
         {
-            let path = ensure_extension(path, "ico");
-            let sizes: &[u32] = &[16, 24, 32, 48, 64, 72, 96, 128, 256];
-            let images: Vec<(u32, RgbaImage)> =
-                sizes.iter().map(|&s| (s, self.generate_image(s))).collect();
+            match font::faces_in_file(&path) {
+                Ok(faces) => {
+                    self.file_faces = Some((path.clone(), faces));
+                    self.set_font(FontSpec::file(path));
+                }
+                Err(e) => self.status_msg = format!("Error loading font: {e}"),
+            }
+        }
+    }
 
-            match write_ico(&path, &images) {
-                Ok(_) => self.status_msg = format!("ICO saved: {}", path.display()),
-                Err(e) => self.status_msg = format!("Error writing ICO: {e}"),
+    /// Faces of the user-supplied font file, if the spec uses one.
+    fn current_file_faces(&mut self) -> &[font::FontFace] {
+        let Some(path) = &self.spec.font.path else {
+            return &[];
+        };
+        if self.file_faces.as_ref().is_none_or(|(p, _)| p != path) {
+            let faces = font::faces_in_file(path).unwrap_or_default();
+            self.file_faces = Some((path.clone(), faces));
+        }
+        self.file_faces.as_ref().map_or(&[], |(_, faces)| faces)
+    }
+
+    fn font_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(format!("Font: {}", self.spec.font))
+            .id_salt("font-panel")
+            .show(ui, |ui| {
+                let bundled = FontSpec::default().to_string();
+                let family = self.spec.font.family.clone();
+                let family_text = match (&family, &self.spec.font.path) {
+                    (Some(family), _) => family.clone(),
+                    (None, Some(_)) => "(font file)".to_string(),
+                    (None, None) => bundled.clone(),
+                };
+
+                // システムフォントの一覧は初めて開いたときに走査される
+                egui::ComboBox::from_label("Family")
+                    .selected_text(family_text)
+                    .height(300.0)
+                    .show_ui(ui, |ui| {
+                        if ui
+                            .selectable_label(self.spec.font.is_bundled(), &bundled)
+                            .clicked()
+                        {
+                            self.set_font(FontSpec::default());
+                        }
+                        for name in font::system_families() {
+                            let selected = family.as_deref() == Some(name);
+                            if ui.selectable_label(selected, name).clicked() && !selected {
+                                self.set_font(FontSpec::family(name, None));
+                            }
+                        }
+                    });
+
+                if let Some(family) = &family {
+                    let style = self.spec.font.style.clone();
+                    egui::ComboBox::from_label("Style")
+                        .selected_text(style.as_deref().unwrap_or("(default)"))
+                        .show_ui(ui, |ui| {
+                            for face in font::faces_of(family) {
+                                let selected = style.as_deref() == Some(face.style.as_str());
+                                if ui.selectable_label(selected, &face.style).clicked() {
+                                    self.set_font(face.to_spec());
+                                }
+                            }
+                        });
+                }
+
+                // コレクション (.ttc) の場合はフェイスを選べるようにする
+                let faces = self.current_file_faces().to_vec();
+                if faces.len() > 1 {
+                    let index = self.spec.font.index;
+                    let label =
+                        |f: &font::FontFace| format!("{}: {} {}", f.index, f.family, f.style);
+                    let selected_text = faces
+                        .iter()
+                        .find(|f| f.index == index)
+                        .map(label)
+                        .unwrap_or_else(|| index.to_string());
+                    egui::ComboBox::from_label("Face")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for face in &faces {
+                                if ui
+                                    .selectable_label(face.index == index, label(face))
+                                    .clicked()
+                                {
+                                    self.set_font(FontSpec::file_face(&face.path, face.index));
+                                }
+                            }
+                        });
+                }
+
+                if ui.button("Load font file…").clicked() {
+                    self.load_font_file();
+                }
+
+                ui.add_space(4.0);
+                self.fallback_panel(ui);
+
+                ui.add_space(4.0);
+                self.features_panel(ui);
+            });
+    }
+
+    /// Tri-state toggles for common OpenType features: font default, on or off.
+    fn features_panel(&mut self, ui: &mut egui::Ui) {
+        const COMMON: [&str; 12] = [
+            "kern", "liga", "clig", "dlig", "calt", "salt", "swsh", "smcp", "ss01", "ss02", "ss03",
+            "ss04",
+        ];
+        egui::CollapsingHeader::new("OpenType features")
+            .id_salt("features-panel")
+            .show(ui, |ui| {
+                let mut tags: Vec<String> = COMMON.iter().map(|t| t.to_string()).collect();
+                for tag in self.spec.font_features.keys() {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                }
+                egui::Grid::new("features-grid").show(ui, |ui| {
+                    for tag in tags {
+                        let mut state = self.spec.font_features.get(&tag).copied();
+                        ui.monospace(&tag);
+                        let mut changed = false;
+                        changed |= ui.selectable_value(&mut state, None, "default").changed();
+                        changed |= ui.selectable_value(&mut state, Some(true), "on").changed();
+                        changed |= ui
+                            .selectable_value(&mut state, Some(false), "off")
+                            .changed();
+                        if changed {
+                            match state {
+                                Some(on) => self.spec.font_features.insert(tag, on),
+                                None => self.spec.font_features.remove(&tag),
+                            };
+                            self.needs_update = true;
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Other tag:");
+                    let edit = ui.add(
+                        egui::TextEdit::singleline(&mut self.new_feature_tag).desired_width(50.0),
+                    );
+                    let submitted =
+                        edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    let valid = is_feature_tag(&self.new_feature_tag);
+                    if (ui.add_enabled(valid, egui::Button::new("Add")).clicked() || submitted)
+                        && valid
+                    {
+                        let tag = std::mem::take(&mut self.new_feature_tag);
+                        self.spec.font_features.insert(tag, true);
+                        self.needs_update = true;
+                    }
+                });
+            });
+    }
+
+    /// Ordered list of fallback fonts used for characters the main font lacks.
+    fn fallback_panel(&mut self, ui: &mut egui::Ui) {
+        ui.label("Fallback fonts:");
+        let mut remove = None;
+        let mut swap = None;
+        let count = self.spec.fallback_fonts.len();
+        for (i, fallback) in self.spec.fallback_fonts.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("  {}. {fallback}", i + 1));
+                if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                    swap = Some((i - 1, i));
+                }
+                if ui
+                    .add_enabled(i + 1 < count, egui::Button::new("⏷"))
+                    .clicked()
+                {
+                    swap = Some((i, i + 1));
+                }
+                if ui.button("✕").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some((a, b)) = swap {
+            self.spec.fallback_fonts.swap(a, b);
+            self.needs_update = true;
+        }
+        if let Some(i) = remove {
+            self.spec.fallback_fonts.remove(i);
+            self.needs_update = true;
+        }
+
+        ui.horizontal(|ui| {
+            let mut added = None;
+            egui::ComboBox::from_id_salt("add-fallback")
+                .selected_text("Add family…")
+                .height(300.0)
+                .show_ui(ui, |ui| {
+                    for name in font::system_families() {
+                        if ui.selectable_label(false, name).clicked() {
+                            added = Some(FontSpec::family(name, None));
+                        }
+                    }
+                });
+            if ui.button("Add file…").clicked() {
+                added = FileDialog::new()
+                    .set_title("Add fallback font")
+                    .add_filter("Fonts", &["ttf", "otf", "ttc", "otc"])
+                    .pick_file()
+                    .map(FontSpec::file);
+            }
+            if let Some(fallback) = added {
+                match font::resolve(&fallback) {
+                    Ok(_) => {
+                        self.spec.fallback_fonts.push(fallback);
+                        self.needs_update = true;
+                    }
+                    Err(e) => self.status_msg = format!("Error loading font: {e}"),
+                }
             }
+        });
+    }
+
+    fn pattern_panel(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Pattern:");
+            egui::ComboBox::from_id_salt("bg-pattern")
+                .selected_text(self.spec.pattern.name())
+                .show_ui(ui, |ui| {
+                    for p in Pattern::ALL {
+                        changed |= ui
+                            .selectable_value(&mut self.spec.pattern, p, p.name())
+                            .changed();
+                    }
+                });
+        });
+        if self.spec.pattern != Pattern::None {
+            let params = &mut self.spec.pattern_params;
+            ui.horizontal(|ui| {
+                ui.label("  Density:");
+                changed |= ui
+                    .add(egui::Slider::new(&mut params.density, 0.25..=4.0).logarithmic(true))
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("  Angle:");
+                changed |= ui
+                    .add(egui::Slider::new(&mut params.angle, 0.0..=180.0).suffix("°"))
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("  Line width:");
+                changed |= ui
+                    .add(egui::Slider::new(&mut params.line_width, 0.0..=8.0).suffix("px"))
+                    .on_hover_text("At 256×256; the pattern scales with the icon")
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("  Highlight:");
+                changed |= ui
+                    .add(egui::Slider::new(&mut params.highlight, 0..=64))
+                    .changed();
+                ui.label("Shadow:");
+                changed |= ui
+                    .add(egui::Slider::new(&mut params.shadow, 0..=64))
+                    .changed();
+            });
+            // プレビューは 256px なので、効果は小さいサイズの書き出しでのみ見える
+            ui.horizontal(|ui| {
+                ui.label("  Below")
+                    .on_hover_text("Smaller ICO entries and PNGs, not the preview");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut params.min_size)
+                            .range(16..=256)
+                            .suffix("px"),
+                    )
+                    .changed();
+                ui.label(":");
+                changed |= ui
+                    .selectable_value(&mut params.hide_small, false, "fewer lines")
+                    .changed();
+                changed |= ui
+                    .selectable_value(&mut params.hide_small, true, "hide")
+                    .changed();
+            });
         }
+        self.needs_update |= changed;
+    }
+
+    fn stroke_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Stroke")
+            .id_salt("stroke-panel")
+            .show(ui, |ui| {
+                let stroke = &mut self.spec.stroke;
+                ui.horizontal(|ui| {
+                    ui.label("Width:");
+                    if ui
+                        .add(egui::Slider::new(&mut stroke.width, 0.0..=0.1).fixed_decimals(3))
+                        .changed()
+                    {
+                        self.needs_update = true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Align:");
+                    for align in StrokeAlign::ALL {
+                        if ui
+                            .selectable_value(&mut stroke.align, align, align.name())
+                            .changed()
+                        {
+                            self.needs_update = true;
+                        }
+                    }
+                });
+                ui.push_id("stroke-fill", |ui| {
+                    if self
+                        .stroke_fill
+                        .show(ui, &mut stroke.fill, &mut self.status_msg)
+                    {
+                        self.needs_update = true;
+                    }
+                });
+            });
+    }
+
+    fn effects_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Effects")
+            .id_salt("effects-panel")
+            .show(ui, |ui| {
+                let mut remove = None;
+                for (i, layer) in self.spec.layers.iter_mut().enumerate() {
+                    let LayerContent::Effect { effect } = &mut layer.content else {
+                        continue;
+                    };
+                    ui.push_id(i, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.strong(effect.kind.name());
+                            if ui.small_button("Remove").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                        // 長い影はずらす量がそのまま影の長さになる
+                        let reach = if effect.kind == EffectKind::LongShadow {
+                            Effect::MAX_OFFSET
+                        } else {
+                            0.2
+                        };
+                        let mut changed = false;
+                        ui.horizontal(|ui| {
+                            ui.label("  Offset:");
+                            for v in &mut effect.offset {
+                                changed |= ui
+                                    .add(
+                                        egui::DragValue::new(v)
+                                            .range(-reach..=reach)
+                                            .speed(0.002)
+                                            .fixed_decimals(3),
+                                    )
+                                    .changed();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("  Blur:");
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut effect.blur, 0.0..=Effect::MAX_BLUR)
+                                        .fixed_decimals(3),
+                                )
+                                .changed();
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("  Spread:");
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut effect.spread, 0.0..=Effect::MAX_SPREAD)
+                                        .fixed_decimals(3),
+                                )
+                                .changed();
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("  Color:");
+                            changed |= color_button(ui, &mut effect.color);
+                            ui.label("Opacity:");
+                            changed |= ui
+                                .add(egui::Slider::new(&mut effect.opacity, 0.0..=1.0))
+                                .changed();
+                        });
+                        self.needs_update |= changed;
+                    });
+                }
+                if let Some(i) = remove {
+                    self.spec.layers.remove(i);
+                    self.needs_update = true;
+                }
+                ui.menu_button("Add effect", |ui| {
+                    for kind in EffectKind::ALL {
+                        if ui.button(kind.name()).clicked() {
+                            let effect = Effect::new(kind);
+                            self.spec.insert_layer(
+                                Layer::new(LayerContent::Effect { effect }),
+                                kind == EffectKind::InnerShadow,
+                            );
+                            self.needs_update = true;
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+    }
+
+    fn images_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Images")
+            .id_salt("images-panel")
+            .show(ui, |ui| {
+                let mut remove = None;
+                for (i, layer) in self.spec.layers.iter_mut().enumerate() {
+                    let LayerContent::Image { image } = &mut layer.content else {
+                        continue;
+                    };
+                    ui.push_id(i, |ui| {
+                        let mut changed = false;
+                        ui.horizontal(|ui| {
+                            ui.strong(file_name(&image.path));
+                            if ui.small_button("Change…").clicked() {
+                                let picked =
+                                    fill_editor::pick_image("Choose image", &mut self.status_msg);
+                                if let Some(picked) = picked {
+                                    image.path = picked.path;
+                                    changed = true;
+                                }
+                            }
+                            if ui.small_button("Remove").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("  Fit:");
+                            for fit in ImageFit::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut image.fit, fit, fit.name())
+                                    .changed();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("  Offset:");
+                            for v in &mut image.offset {
+                                changed |= ui
+                                    .add(
+                                        egui::DragValue::new(v)
+                                            .range(-1.0..=1.0)
+                                            .speed(0.005)
+                                            .fixed_decimals(3),
+                                    )
+                                    .changed();
+                            }
+                            ui.label("Scale:");
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut image.scale)
+                                        .range(0.05..=8.0)
+                                        .speed(0.01)
+                                        .fixed_decimals(2),
+                                )
+                                .changed();
+                        });
+                        self.needs_update |= changed;
+                    });
+                }
+                if let Some(i) = remove {
+                    self.spec.layers.remove(i);
+                    self.needs_update = true;
+                }
+                if ui.button("Add image…").clicked() {
+                    if let Some(picked) =
+                        fill_editor::pick_image("Choose image", &mut self.status_msg)
+                    {
+                        let image = ImageLayer {
+                            path: picked.path,
+                            ..ImageLayer::default()
+                        };
+                        self.spec
+                            .insert_layer(Layer::new(LayerContent::Image { image }), false);
+                        self.needs_update = true;
+                    }
+                }
+            });
+    }
+
+    /// The layer stack, top layer first: visibility, order, opacity and
+    /// blend mode. The settings of each layer are in its own panel.
+    fn layers_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Layers")
+            .id_salt("layers-panel")
+            .show(ui, |ui| {
+                let count = self.spec.layers.len();
+                let mut swap = None;
+                let mut remove = None;
+                for i in (0..count).rev() {
+                    let layer = &mut self.spec.layers[i];
+                    ui.push_id(i, |ui| {
+                        let mut changed = false;
+                        ui.horizontal(|ui| {
+                            changed |= ui.checkbox(&mut layer.visible, "").changed();
+                            let name = match &layer.content {
+                                LayerContent::Image { image } => file_name(&image.path),
+                                LayerContent::Effect { effect } => effect.kind.name().to_string(),
+                                content => content.name().to_string(),
+                            };
+                            ui.strong(name);
+                            let up = ui.add_enabled(i + 1 < count, egui::Button::new("▲").small());
+                            if up.clicked() {
+                                swap = Some(i);
+                            }
+                            let down = ui.add_enabled(i > 0, egui::Button::new("▼").small());
+                            if down.clicked() {
+                                swap = Some(i - 1);
+                            }
+                            if !layer.is_builtin() && ui.small_button("Remove").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("  Opacity:");
+                            changed |= ui
+                                .add(egui::Slider::new(&mut layer.opacity, 0.0..=1.0))
+                                .changed();
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("  Blend:");
+                            egui::ComboBox::from_id_salt("layer-blend")
+                                .selected_text(layer.blend.name())
+                                .show_ui(ui, |ui| {
+                                    for mode in BlendMode::ALL {
+                                        changed |= ui
+                                            .selectable_value(&mut layer.blend, mode, mode.name())
+                                            .changed();
+                                    }
+                                });
+                            changed |= ui
+                                .checkbox(&mut layer.clip, "Clip")
+                                .on_hover_text(
+                                    "Paint only over the layers below; turn it on to \
+                                     keep a shadow or glow on the plate",
+                                )
+                                .changed();
+                        });
+                        self.needs_update |= changed;
+                    });
+                }
+                // swap は下のレイヤーの番号：i と i + 1 を入れ替える
+                if let Some(i) = swap {
+                    self.spec.layers.swap(i, i + 1);
+                    self.needs_update = true;
+                }
+                if let Some(i) = remove {
+                    self.spec.layers.remove(i);
+                    self.needs_update = true;
+                }
+            });
+    }
+
+    fn depth_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Bevel & extrusion")
+            .id_salt("depth-panel")
+            .show(ui, |ui| {
+                let mut changed = false;
+                let bevel = &mut self.spec.bevel;
+                ui.horizontal(|ui| {
+                    ui.label("Bevel:");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut bevel.size, 0.0..=0.1).fixed_decimals(3))
+                        .changed();
+                });
+                if !bevel.is_none() {
+                    ui.horizontal(|ui| {
+                        ui.label("  Style:");
+                        for style in BevelStyle::ALL {
+                            changed |= ui
+                                .selectable_value(&mut bevel.style, style, style.name())
+                                .changed();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("  Depth:");
+                        changed |= ui
+                            .add(egui::Slider::new(&mut bevel.depth, 0.0..=1.0))
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("  Light:");
+                        changed |= ui
+                            .add(egui::Slider::new(&mut bevel.angle, 0.0..=360.0).suffix("°"))
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("  Soften:");
+                        changed |= ui
+                            .add(egui::Slider::new(&mut bevel.soften, 0.0..=0.05).fixed_decimals(3))
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("  Highlight:");
+                        changed |= color_button(ui, &mut bevel.highlight);
+                        ui.label("Shadow:");
+                        changed |= color_button(ui, &mut bevel.shadow);
+                    });
+                }
+
+                let extrusion = &mut self.spec.extrusion;
+                ui.horizontal(|ui| {
+                    ui.label("Extrusion:");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut extrusion.depth, 0.0..=0.2).fixed_decimals(3))
+                        .changed();
+                });
+                if !extrusion.is_none() {
+                    ui.horizontal(|ui| {
+                        ui.label("  Direction:");
+                        changed |= ui
+                            .add(egui::Slider::new(&mut extrusion.angle, 0.0..=360.0).suffix("°"))
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("  Color:");
+                        changed |= color_button(ui, &mut extrusion.color);
+                        ui.label("Shade:");
+                        changed |= ui
+                            .add(egui::Slider::new(&mut extrusion.shade, 0.0..=1.0))
+                            .changed();
+                    });
+                }
+                self.needs_update |= changed;
+            });
+    }
+
+    fn rendering_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Rendering")
+            .id_salt("rendering-panel")
+            .show(ui, |ui| {
+                if ui
+                    .checkbox(&mut self.spec.antialias, "Anti-aliasing")
+                    .changed()
+                {
+                    self.needs_update = true;
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Supersampling:");
+                    if ui
+                        .add(
+                            egui::Slider::new(
+                                &mut self.spec.supersample,
+                                1..=IconSpec::MAX_SUPERSAMPLE,
+                            )
+                            .suffix("×"),
+                        )
+                        .changed()
+                    {
+                        self.needs_update = true;
+                    }
+                });
+                // プレビューは 256px なので、効果は小さいサイズの書き出しでのみ見える
+                ui.checkbox(
+                    &mut self.spec.snap_stems,
+                    format!(
+                        "Snap stems to pixels (≤ {}px)",
+                        IconSpec::STEM_SNAP_MAX_SIZE
+                    ),
+                )
+                .on_hover_text("Applies to ICO entries and PNGs of that size, not the preview");
+            });
     }
 
     fn update_preview(&mut self, ctx: &egui::Context) {
-        let img = self.generate_image(256);
+        let img = render(&self.spec, 256);
         let color_image = ColorImage::from_rgba_unmultiplied([256, 256], img.as_raw());
 
         match &mut self.texture {
@@ -340,68 +906,179 @@ impl eframe::App for IconMarkerApp {
                 // Left: controls
                 ui.vertical(|ui| {
                     ui.set_width(300.0);
+                    // 固定サイズのウィンドウでも保存ボタンと状態表示まで届くようにする
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        ui.label("Text:");
+                        if ui
+                            .add(egui::TextEdit::multiline(&mut self.spec.text).desired_rows(2))
+                            .changed()
+                        {
+                            self.needs_update = true;
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Direction:");
+                            for mode in WritingMode::ALL {
+                                if ui
+                                    .selectable_value(
+                                        &mut self.spec.writing_mode,
+                                        mode,
+                                        mode.name(),
+                                    )
+                                    .changed()
+                                {
+                                    self.needs_update = true;
+                                }
+                            }
+                        });
+                        if self.spec.text.contains('\n') {
+                            let vertical = self.spec.writing_mode == WritingMode::Vertical;
+                            ui.horizontal(|ui| {
+                                ui.label("Align:");
+                                for align in TextAlign::ALL {
+                                    // 縦書きでは列の上下方向に揃える
+                                    let label = match (vertical, align) {
+                                        (true, TextAlign::Left) => "top",
+                                        (true, TextAlign::Right) => "bottom",
+                                        _ => align.name(),
+                                    };
+                                    if ui
+                                        .selectable_value(&mut self.spec.text_align, align, label)
+                                        .changed()
+                                    {
+                                        self.needs_update = true;
+                                    }
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Line height:");
+                                if ui
+                                    .add(
+                                        egui::Slider::new(&mut self.spec.line_height, 0.5..=2.0)
+                                            .fixed_decimals(2),
+                                    )
+                                    .changed()
+                                {
+                                    self.needs_update = true;
+                                }
+                            });
+                        }
 
-                    ui.label("Text:");
-                    if ui.text_edit_singleline(&mut self.text).changed() {
-                        self.needs_update = true;
-                    }
+                        ui.add_space(4.0);
+                        self.font_panel(ui);
 
-                    ui.add_space(8.0);
-                    ui.horizontal(|ui| {
+                        ui.add_space(8.0);
                         ui.label("Background:");
-                        if color_edit_button_srgba(ui, &mut self.bg_color, Alpha::Opaque).changed()
-                        {
-                            self.needs_update = true;
+                        // 文字の塗りのエディタとウィジェットの ID が重ならないようにする
+                        ui.push_id("bg-fill", |ui| {
+                            if self.bg_fill.show(
+                                ui,
+                                &mut self.spec.background,
+                                &mut self.status_msg,
+                            ) {
+                                self.needs_update = true;
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("  Shape:");
+                            for shape in BackgroundShape::ALL {
+                                if ui
+                                    .selectable_value(&mut self.spec.bg_shape, shape, shape.name())
+                                    .changed()
+                                {
+                                    self.needs_update = true;
+                                }
+                            }
+                        });
+                        if self.spec.bg_shape == BackgroundShape::Rounded {
+                            ui.horizontal(|ui| {
+                                ui.label("  Corner radius:");
+                                if ui
+                                    .add(
+                                        egui::Slider::new(&mut self.spec.corner_radius, 0.0..=0.5)
+                                            .fixed_decimals(2),
+                                    )
+                                    .changed()
+                                {
+                                    self.needs_update = true;
+                                }
+                            });
                         }
-                    });
 
-                    ui.add_space(4.0);
-                    if ui
-                        .checkbox(&mut self.chevron_on, "Chevron pattern")
-                        .changed()
-                    {
-                        self.needs_update = true;
-                    }
+                        ui.add_space(4.0);
+                        self.pattern_panel(ui);
+                        self.images_panel(ui);
+                        self.layers_panel(ui);
 
-                    ui.add_space(8.0);
-                    ui.label("Text gradient:");
-                    ui.horizontal(|ui| {
-                        ui.label("  Start:");
-                        if color_edit_button_srgba(ui, &mut self.grad_start, Alpha::Opaque)
-                            .changed()
+                        ui.add_space(8.0);
+                        ui.label("Text fill:");
+                        if self
+                            .text_fill
+                            .show(ui, &mut self.spec.fill, &mut self.status_msg)
                         {
                             self.needs_update = true;
                         }
-                        ui.label("  End:");
-                        if color_edit_button_srgba(ui, &mut self.grad_end, Alpha::Opaque).changed()
+                        ui.horizontal(|ui| {
+                            ui.label("  Blending:");
+                            for blending in Blending::ALL {
+                                if ui
+                                    .selectable_value(
+                                        &mut self.spec.blending,
+                                        blending,
+                                        blending.name(),
+                                    )
+                                    .changed()
+                                {
+                                    self.needs_update = true;
+                                }
+                            }
+                        });
+
+                        ui.add_space(4.0);
+                        self.stroke_panel(ui);
+                        self.effects_panel(ui);
+                        self.depth_panel(ui);
+
+                        ui.add_space(8.0);
+                        ui.label("Padding:");
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut self.spec.padding, 0.0..=0.4)
+                                    .fixed_decimals(2),
+                            )
+                            .changed()
                         {
                             self.needs_update = true;
                         }
-                    });
 
-                    ui.add_space(8.0);
-                    ui.label("Padding:");
-                    if ui
-                        .add(egui::Slider::new(&mut self.padding, 0.0..=0.4).fixed_decimals(2))
-                        .changed()
-                    {
-                        self.needs_update = true;
-                    }
+                        ui.add_space(4.0);
+                        self.rendering_panel(ui);
 
-                    ui.add_space(16.0);
-                    ui.horizontal(|ui| {
-                        if ui.button("Save PNG").clicked() {
-                            self.save_png();
-                        }
-                        if ui.button("Save ICO").clicked() {
-                            self.save_ico();
+                        ui.add_space(16.0);
+                        ui.horizontal(|ui| {
+                            if ui.button("Save PNG").clicked() {
+                                self.save_png();
+                            }
+                            if ui.button("Save ICO").clicked() {
+                                self.save_ico();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button("Open project…").clicked() {
+                                self.open_project();
+                            }
+                            if ui.button("Save project…").clicked() {
+                                self.save_project();
+                            }
+                            if ui.button("Open from PNG…").clicked() {
+                                self.open_from_png();
+                            }
+                        });
+
+                        if !self.status_msg.is_empty() {
+                            ui.add_space(8.0);
+                            ui.label(&self.status_msg);
                         }
                     });
-
-                    if !self.status_msg.is_empty() {
-                        ui.add_space(8.0);
-                        ui.label(&self.status_msg);
-                    }
                 });
 
                 ui.separator();
@@ -410,7 +1087,10 @@ impl eframe::App for IconMarkerApp {
                 ui.vertical(|ui| {
                     ui.label("Preview (256x256):");
                     if let Some(tex) = &self.texture {
-                        ui.image((tex.id(), egui::vec2(256.0, 256.0)));
+                        let (rect, _) =
+                            ui.allocate_exact_size(egui::vec2(256.0, 256.0), egui::Sense::hover());
+                        paint_checkerboard(ui.painter(), rect);
+                        egui::Image::new((tex.id(), rect.size())).paint_at(ui, rect);
                     }
                 });
             });
@@ -422,68 +1102,23 @@ impl eframe::App for IconMarkerApp {
     }
 }
 
-/// Write an ICO file with multiple sizes.
-/// Each image must already be rendered at the correct size.
-/// All sizes are stored as PNG (Vista+ compatible). This avoids BMP DIB header
-/// issues (biHeight doubling, AND mask requirements) that cause rendering glitches
-/// on Windows, such as the top of the icon being clipped.
-fn write_ico(path: &std::path::Path, images: &[(u32, RgbaImage)]) -> std::io::Result<()> {
-    struct IcoEntry {
-        width: u8, // 0 means 256
-        height: u8,
-        planes: u16,
-        bpp: u16,
-        data: Vec<u8>,
-    }
-
-    let mut entries = Vec::new();
-
-    for (s, resized) in images {
-        let s = *s;
-
-        // Encode all sizes as PNG — clean, no BMP header pitfalls.
-        let mut buf = Cursor::new(Vec::new());
-        DynamicImage::ImageRgba8(resized.clone())
-            .write_to(&mut buf, ImageFormat::Png)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        entries.push(IcoEntry {
-            width: if s >= 256 { 0 } else { s as u8 },
-            height: if s >= 256 { 0 } else { s as u8 },
-            planes: 1,
-            bpp: 32,
-            data: buf.into_inner(),
-        });
-    }
-
-    // Write ICO file
-    let mut file = File::create(path)?;
-    let count = entries.len() as u16;
-
-    // ICONDIR header (6 bytes)
-    file.write_all(&0u16.to_le_bytes())?; // idReserved
-    file.write_all(&1u16.to_le_bytes())?; // idType = ICO
-    file.write_all(&count.to_le_bytes())?; // idCount
-
-    // Calculate data offsets
-    let dir_end = 6u32 + 16 * count as u32;
-    let mut offset = dir_end;
-
-    // ICONDIRENTRY for each image (16 bytes each)
-    for entry in &entries {
-        file.write_all(&[entry.width, entry.height, 0, 0])?; // w, h, colorCount, reserved
-        file.write_all(&entry.planes.to_le_bytes())?; // wPlanes
-        file.write_all(&entry.bpp.to_le_bytes())?; // wBitCount
-        file.write_all(&(entry.data.len() as u32).to_le_bytes())?; // dwBytesInRes
-        file.write_all(&offset.to_le_bytes())?; // dwImageOffset
-        offset += entry.data.len() as u32;
-    }
-
-    // Image data
-    for entry in &entries {
-        file.write_all(&entry.data)?;
+/// Checkerboard behind the preview, so that transparent parts show.
+fn paint_checkerboard(painter: &egui::Painter, rect: egui::Rect) {
+    const CELL: f32 = 16.0;
+    let light = egui::Color32::from_gray(204);
+    let dark = egui::Color32::from_gray(153);
+    painter.rect_filled(rect, 0.0, light);
+    let (cols, rows) = (
+        (rect.width() / CELL).ceil() as usize,
+        (rect.height() / CELL).ceil() as usize,
+    );
+    for row in 0..rows {
+        for col in (row % 2..cols).step_by(2) {
+            let min = rect.min + egui::vec2(col as f32 * CELL, row as f32 * CELL);
+            let cell = egui::Rect::from_min_size(min, egui::vec2(CELL, CELL)).intersect(rect);
+            painter.rect_filled(cell, 0.0, dark);
+        }
     }
-
-    Ok(())
 }
 
 fn ensure_extension(path: PathBuf, ext: &str) -> PathBuf {
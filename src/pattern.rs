@@ -0,0 +1,176 @@
+//! Tiled patterns, drawn on the background or used as a text fill.
+//!
+//! Every pattern is a field of line coverage over the canvas. On the
+//! background the lines lighten it and the pixels just under them are
+//! darkened, so that they look like raised ridges lit from above.
+//!
+//! Pattern lengths are in pixels of a [`PatternParams::REFERENCE_SIZE`]
+//! icon; other sizes see the same pattern scaled, down to
+//! [`PatternParams::min_size`].
+
+use crate::spec::{Pattern, PatternParams};
+use image::RgbaImage;
+use std::f32::consts::TAU;
+
+/// 線の間隔（基準サイズでの px）
+const CHEVRON_SPACING: f32 = 6.0;
+/// ジグザグ1周期の幅（px）
+const CHEVRON_PERIOD: f32 = 20.0;
+const STRIPE_SPACING: f32 = 8.0;
+const DOT_CELL: f32 = 10.0;
+/// Radius of a dot drawn with a line width of 0.
+const DOT_RADIUS: f32 = 1.5;
+const GRID_CELL: f32 = 12.0;
+/// Width of a hexagon, flat side to flat side.
+const HEX_CELL: f32 = 14.0;
+const WAVE_SPACING: f32 = 8.0;
+const WAVE_LENGTH: f32 = 24.0;
+const WAVE_AMPLITUDE: f32 = 2.0;
+const CHECKER_CELL: f32 = 12.0;
+const RING_SPACING: f32 = 8.0;
+
+/// A pattern with its settings, ready to sample on a `size`×`size` canvas.
+pub(crate) struct Tiling {
+    pattern: Pattern,
+    /// Pixels per pattern unit.
+    scale: f32,
+    density: f32,
+    /// In pixels.
+    line_width: f32,
+    sin: f32,
+    cos: f32,
+    center: f32,
+}
+
+impl Tiling {
+    pub(crate) fn new(pattern: Pattern, params: &PatternParams, size: u32) -> Self {
+        let (sin, cos) = params.angle.to_radians().sin_cos();
+        let hidden = size < params.min_size && params.hide_small;
+        let scale = size.max(params.min_size) as f32 / PatternParams::REFERENCE_SIZE as f32;
+        Self {
+            pattern: if hidden { Pattern::None } else { pattern },
+            scale,
+            density: params.density.max(0.01),
+            line_width: params.line_width.max(0.0) * scale,
+            sin,
+            cos,
+            center: size as f32 / 2.0,
+        }
+    }
+
+    /// Pattern coordinates of the point (`x`, `y`): rotated and scaled
+    /// about the centre of the canvas, which is the centre of the pattern
+    /// at every size.
+    fn to_pattern(&self, x: f32, y: f32) -> (f32, f32) {
+        let origin = PatternParams::REFERENCE_SIZE as f32 / 2.0;
+        let k = self.density / self.scale;
+        let (dx, dy) = (x - self.center, y - self.center);
+        let (u, v) = (dx * self.cos + dy * self.sin, dy * self.cos - dx * self.sin);
+        (u * k + origin, v * k + origin)
+    }
+
+    /// Coverage (0.0..=1.0) of the pattern's lines at the pixel (`x`, `y`).
+    pub(crate) fn coverage(&self, x: f32, y: f32) -> f32 {
+        let (u, v) = self.to_pattern(x, y);
+        // パターン座標での長さを px に直す係数
+        let to_px = self.scale / self.density;
+        // 最も近い線までの距離（パターン座標）
+        let distance = match self.pattern {
+            Pattern::None => return 0.0,
+            Pattern::Chevron => {
+                // 三角波（繰り返しジグザグ）で複数のV字折り返しを作る
+                let zigzag = (u.rem_euclid(CHEVRON_PERIOD) - CHEVRON_PERIOD / 2.0).abs();
+                band(v + zigzag, CHEVRON_SPACING)
+            }
+            Pattern::Stripes => band(v, STRIPE_SPACING),
+            Pattern::Dots => band(u, DOT_CELL).hypot(band(v, DOT_CELL)) - DOT_RADIUS,
+            Pattern::Grid => band(u, GRID_CELL).min(band(v, GRID_CELL)),
+            Pattern::Hexagons => hex_edge_distance(u / HEX_CELL, v / HEX_CELL) * HEX_CELL,
+            Pattern::Waves => band(
+                v + WAVE_AMPLITUDE * (u / WAVE_LENGTH * TAU).sin(),
+                WAVE_SPACING,
+            ),
+            Pattern::Checkerboard => {
+                let cell = (u / CHECKER_CELL).floor() + (v / CHECKER_CELL).floor();
+                let edge = band(u, CHECKER_CELL).min(band(v, CHECKER_CELL)) * to_px;
+                return if cell.rem_euclid(2.0) == 1.0 {
+                    (0.5 + edge).min(1.0)
+                } else {
+                    (0.5 - edge).max(0.0)
+                };
+            }
+            Pattern::Noise => return grain(u, v).max(0.0),
+            Pattern::Rings => {
+                let origin = PatternParams::REFERENCE_SIZE as f32 / 2.0;
+                band((u - origin).hypot(v - origin), RING_SPACING)
+            }
+        };
+        // 距離を px に直し、線幅の縁を 1px の傾斜で滑らかにする
+        (self.line_width / 2.0 + 0.5 - distance * to_px).clamp(0.0, 1.0)
+    }
+
+    /// Strength (0.0..=1.0) of the shadow at the pixel (`x`, `y`): just
+    /// under a line, with the light from the top.
+    pub(crate) fn shadow(&self, x: f32, y: f32) -> f32 {
+        if self.pattern == Pattern::Noise {
+            let (u, v) = self.to_pattern(x, y);
+            return (-grain(u, v)).max(0.0);
+        }
+        // 影は基準サイズで 1px 下、縮小しても 1px より細くしない
+        let below = self.scale.max(1.0);
+        self.coverage(x, y - below) * (1.0 - self.coverage(x, y))
+    }
+}
+
+/// Distance from `t` to the nearest multiple of `spacing`.
+fn band(t: f32, spacing: f32) -> f32 {
+    let phase = t.rem_euclid(spacing);
+    phase.min(spacing - phase)
+}
+
+/// Distance from (`x`, `y`) to the nearest edge of a tiling of hexagons one
+/// unit wide, flat side to flat side.
+fn hex_edge_distance(x: f32, y: f32) -> f32 {
+    const ROW: f32 = 1.732_050_8;
+    // 六角形の中心は 2 つの長方形格子に並ぶので、近い方の中心を使う
+    let wrap = |x: f32, y: f32| (x.rem_euclid(1.0) - 0.5, y.rem_euclid(ROW) - ROW / 2.0);
+    let a = wrap(x, y);
+    let b = wrap(x - 0.5, y - ROW / 2.0);
+    let (gx, gy) = if a.0.hypot(a.1) < b.0.hypot(b.1) {
+        a
+    } else {
+        b
+    };
+    let (gx, gy) = (gx.abs(), gy.abs());
+    0.5 - (gx * 0.5 + gy * ROW / 2.0).max(gx)
+}
+
+/// Random value in -1.0..=1.0 for the grain cell containing (`u`, `v`).
+fn grain(u: f32, v: f32) -> f32 {
+    let (i, j) = (u.floor() as i32 as u32, v.floor() as i32 as u32);
+    let mut h = i.wrapping_mul(0x9E37_79B1) ^ j.wrapping_mul(0x85EB_CA77);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2C1B_3C6D);
+    h ^= h >> 12;
+    (h & 0xFFFF) as f32 / 32767.5 - 1.0
+}
+
+/// Draw `pattern` over the background in `img`.
+pub(crate) fn draw_pattern(img: &mut RgbaImage, pattern: Pattern, params: &PatternParams) {
+    let tiling = Tiling::new(pattern, params, img.width());
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let (x, y) = (x as f32, y as f32);
+        let light = tiling.coverage(x, y);
+        let dark = if light < 1.0 {
+            tiling.shadow(x, y)
+        } else {
+            0.0
+        };
+        let delta = (params.highlight as f32 * light - params.shadow as f32 * dark).round() as i32;
+        if delta != 0 {
+            for c in &mut pixel.0[..3] {
+                *c = (*c as i32 + delta).clamp(0, 255) as u8;
+            }
+        }
+    }
+}
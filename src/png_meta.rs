@@ -0,0 +1,113 @@
+//! Embeds the [`IconSpec`] in exported PNG files so an icon can be reopened
+//! with the exact settings that produced it.
+//!
+//! The spec is stored as a [project](crate::project) document in an `iTXt`
+//! chunk, so files from older releases are migrated like project files.
+
+use crate::project::{self, ProjectError};
+use crate::spec::IconSpec;
+use image::RgbaImage;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+/// `iTXt` keyword that carries the spec.
+pub const KEYWORD: &str = "IconMarker";
+
+#[derive(Debug)]
+pub enum PngMetaError {
+    Io(std::io::Error),
+    Encode(png::EncodingError),
+    Decode(png::DecodingError),
+    Project(ProjectError),
+    /// The PNG was not written by IconMarker.
+    NoSpec,
+}
+
+impl fmt::Display for PngMetaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngMetaError::Io(e) => write!(f, "{e}"),
+            PngMetaError::Encode(e) => write!(f, "cannot encode PNG: {e}"),
+            PngMetaError::Decode(e) => write!(f, "cannot read PNG: {e}"),
+            PngMetaError::Project(e) => write!(f, "{e}"),
+            PngMetaError::NoSpec => write!(f, "PNG has no IconMarker settings"),
+        }
+    }
+}
+
+impl std::error::Error for PngMetaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PngMetaError::Io(e) => Some(e),
+            PngMetaError::Encode(e) => Some(e),
+            PngMetaError::Decode(e) => Some(e),
+            PngMetaError::Project(e) => Some(e),
+            PngMetaError::NoSpec => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PngMetaError {
+    fn from(e: std::io::Error) -> Self {
+        PngMetaError::Io(e)
+    }
+}
+
+impl From<png::EncodingError> for PngMetaError {
+    fn from(e: png::EncodingError) -> Self {
+        PngMetaError::Encode(e)
+    }
+}
+
+impl From<png::DecodingError> for PngMetaError {
+    fn from(e: png::DecodingError) -> Self {
+        PngMetaError::Decode(e)
+    }
+}
+
+impl From<ProjectError> for PngMetaError {
+    fn from(e: ProjectError) -> Self {
+        PngMetaError::Project(e)
+    }
+}
+
+/// Encode `img` as PNG with `spec` stored in an `iTXt` chunk.
+pub fn write_png<W: Write>(w: W, img: &RgbaImage, spec: &IconSpec) -> Result<(), PngMetaError> {
+    let mut encoder = png::Encoder::new(w, img.width(), img.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.add_itxt_chunk(KEYWORD.to_string(), project::to_string(spec)?)?;
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(img.as_raw())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Save `img` to `path` as PNG with `spec` embedded.
+pub fn save_png(path: &Path, img: &RgbaImage, spec: &IconSpec) -> Result<(), PngMetaError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_png(&mut file, img, spec)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Read the spec embedded by [`write_png`].
+pub fn read_spec<R: Read + Seek>(r: R) -> Result<IconSpec, PngMetaError> {
+    let decoder = png::Decoder::new(BufReader::new(r));
+    let reader = decoder.read_info()?;
+    let chunk = reader
+        .info()
+        .utf8_text
+        .iter()
+        .find(|c| c.keyword == KEYWORD)
+        .ok_or(PngMetaError::NoSpec)?;
+    let text = chunk.get_text()?;
+    Ok(project::from_str(&text)?)
+}
+
+/// Read the spec embedded in the PNG at `path`.
+pub fn load_spec(path: &Path) -> Result<IconSpec, PngMetaError> {
+    read_spec(File::open(path)?)
+}
@@ -0,0 +1,266 @@
+//! Project files: an [`IconSpec`] saved as human-editable TOML.
+//!
+//! ```toml
+//! version = 5
+//! text = "G"
+//! padding = 0.1
+//! pattern = "chevron"
+//! sizes = [16, 24, 32, 48, 64, 72, 96, 128, 256]
+//!
+//! [background]
+//! type = "solid"
+//! color = "#F2DCC6"
+//!
+//! [fill]
+//! type = "gradient"
+//! kind = "linear"
+//! angle = 45.0
+//!
+//! [[fill.stops]]
+//! offset = 0.0
+//! color = "#785ADC"
+//!
+//! [[fill.stops]]
+//! offset = 1.0
+//! color = "#14AA82"
+//!
+//! [[layers]]
+//! type = "plate"
+//!
+//! [[layers]]
+//! type = "pattern"
+//! clip = true
+//!
+//! [[layers]]
+//! type = "text"
+//! opacity = 0.9
+//! blend = "multiply"
+//! ```
+//!
+//! Keys this version does not know are ignored, so a file written by a newer
+//! release still opens as long as its `version` is supported. Older versions
+//! are upgraded by [`migrate`] before deserializing.
+
+use crate::spec::{Gradient, IconSpec};
+use std::fmt;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Schema version written by [`to_string`].
+pub const CURRENT_VERSION: u32 = 5;
+
+/// File extension used for project files.
+pub const EXTENSION: &str = "toml";
+
+#[derive(Debug)]
+pub enum ProjectError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    /// The file was written by a newer release with an incompatible schema.
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::Io(e) => write!(f, "{e}"),
+            ProjectError::Parse(e) => write!(f, "invalid project file: {e}"),
+            ProjectError::Serialize(e) => write!(f, "cannot serialize project: {e}"),
+            ProjectError::UnsupportedVersion(v) => write!(
+                f,
+                "project version {v} is newer than supported version {CURRENT_VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProjectError::Io(e) => Some(e),
+            ProjectError::Parse(e) => Some(e),
+            ProjectError::Serialize(e) => Some(e),
+            ProjectError::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ProjectError {
+    fn from(e: std::io::Error) -> Self {
+        ProjectError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for ProjectError {
+    fn from(e: toml::de::Error) -> Self {
+        ProjectError::Parse(e)
+    }
+}
+
+impl From<toml::ser::Error> for ProjectError {
+    fn from(e: toml::ser::Error) -> Self {
+        ProjectError::Serialize(e)
+    }
+}
+
+/// Serialize `spec` as a versioned TOML document.
+pub fn to_string(spec: &IconSpec) -> Result<String, ProjectError> {
+    let mut doc = Table::new();
+    doc.insert(
+        "version".to_string(),
+        Value::Integer(CURRENT_VERSION.into()),
+    );
+    let Value::Table(fields) = Value::try_from(spec)? else {
+        unreachable!("IconSpec serializes to a table");
+    };
+    doc.extend(fields);
+    for (_, value) in doc.iter_mut() {
+        tidy_floats(value);
+    }
+    Ok(toml::to_string(&doc)?)
+}
+
+/// Parse a project document, upgrading older schema versions.
+pub fn from_str(s: &str) -> Result<IconSpec, ProjectError> {
+    let mut doc: Table = s.parse()?;
+    // version が無いファイルは最初のスキーマとして扱う
+    let version = match doc.remove("version") {
+        Some(Value::Integer(v)) => u32::try_from(v).unwrap_or(u32::MAX),
+        _ => 1,
+    };
+    if version > CURRENT_VERSION {
+        return Err(ProjectError::UnsupportedVersion(version));
+    }
+    migrate(&mut doc, version);
+    Ok(Value::Table(doc).try_into()?)
+}
+
+pub fn save(path: &Path, spec: &IconSpec) -> Result<(), ProjectError> {
+    std::fs::write(path, to_string(spec)?)?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<IconSpec, ProjectError> {
+    from_str(&std::fs::read_to_string(path)?)
+}
+
+/// Upgrade a document written with schema `from` to [`CURRENT_VERSION`].
+///
+/// Each step rewrites the table in place for exactly one version bump
+/// (`if from < 2 { ... }`), so adding a version means adding one step here.
+fn migrate(doc: &mut Table, from: u32) {
+    if from < 2 {
+        // v1 は 2 色の斜めグラデーション（grad_start / grad_end）だけだった
+        let start = doc.remove("grad_start");
+        let end = doc.remove("grad_end");
+        let space = doc.remove("gradient_space");
+        if start.is_some() || end.is_some() || space.is_some() {
+            let Ok(Value::Table(mut gradient)) = Value::try_from(Gradient::default()) else {
+                unreachable!("Gradient serializes to a table");
+            };
+            if let Some(Value::Array(stops)) = gradient.get_mut("stops") {
+                for (stop, color) in stops.iter_mut().zip([start, end]) {
+                    if let (Value::Table(stop), Some(color)) = (stop, color) {
+                        stop.insert("color".to_string(), color);
+                    }
+                }
+            }
+            if let Some(space) = space {
+                gradient.insert("space".to_string(), space);
+            }
+            doc.insert("gradient".to_string(), Value::Table(gradient));
+        }
+    }
+    if from < 3 {
+        // v2 の文字は常にグラデーションだった。塗りの種類は type で区別する
+        if let Some(Value::Table(gradient)) = doc.remove("gradient") {
+            let mut fill = Table::new();
+            fill.insert("type".to_string(), Value::String("gradient".to_string()));
+            fill.extend(gradient);
+            doc.insert("fill".to_string(), Value::Table(fill));
+        }
+    }
+    if from < 4 {
+        // v3 の背景は単色（bg_color）だけだった
+        if let Some(color) = doc.remove("bg_color") {
+            let mut background = Table::new();
+            background.insert("type".to_string(), Value::String("solid".to_string()));
+            background.insert("color".to_string(), color);
+            doc.insert("background".to_string(), Value::Table(background));
+        }
+    }
+    if from < 5 {
+        // v4 までは描く順番が決まっていた：板・模様・背面の画像・影・文字・
+        // 内側の影・前面の画像。背面の画像は板の形で切り抜かれていた
+        let clip_images = doc.get("bg_shape").and_then(Value::as_str) != Some("none");
+        let layer = |kind: &str| {
+            let mut layer = Table::new();
+            layer.insert("type".to_string(), Value::String(kind.to_string()));
+            layer
+        };
+        let mut images = (Vec::new(), Vec::new());
+        if let Some(Value::Array(entries)) = doc.remove("images") {
+            for entry in entries {
+                let Value::Table(mut image) = entry else {
+                    continue;
+                };
+                let mut table = layer("image");
+                for key in ["opacity", "blend"] {
+                    if let Some(value) = image.remove(key) {
+                        table.insert(key.to_string(), value);
+                    }
+                }
+                let above = image.remove("above_text").and_then(|v| v.as_bool()) == Some(true);
+                table.insert("clip".to_string(), Value::Boolean(!above && clip_images));
+                table.insert("image".to_string(), Value::Table(image));
+                if above {
+                    images.1.push(table);
+                } else {
+                    images.0.push(table);
+                }
+            }
+        }
+        // 効果は切り抜かれていなかったので clip は付けない
+        let mut effects = (Vec::new(), Vec::new());
+        if let Some(Value::Array(entries)) = doc.remove("effects") {
+            for effect in entries {
+                let inner = effect.get("kind").and_then(Value::as_str) == Some("inner-shadow");
+                let mut table = layer("effect");
+                table.insert("effect".to_string(), effect);
+                if inner {
+                    effects.1.push(table);
+                } else {
+                    effects.0.push(table);
+                }
+            }
+        }
+        let mut pattern = layer("pattern");
+        pattern.insert("clip".to_string(), Value::Boolean(true));
+        let layers = [layer("plate"), pattern]
+            .into_iter()
+            .chain(images.0)
+            .chain(effects.0)
+            .chain([layer("text")])
+            .chain(effects.1)
+            .chain(images.1)
+            .map(Value::Table)
+            .collect();
+        doc.insert("layers".to_string(), Value::Array(layers));
+    }
+}
+
+/// Spec fields are `f32`; print them with their shortest `f32` form so that
+/// `0.1` is written as `0.1` rather than `0.10000000149011612`.
+fn tidy_floats(value: &mut Value) {
+    match value {
+        Value::Float(f) => {
+            if let Ok(short) = (*f as f32).to_string().parse() {
+                *f = short;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(tidy_floats),
+        Value::Table(table) => table.iter_mut().for_each(|(_, v)| tidy_floats(v)),
+        _ => {}
+    }
+}
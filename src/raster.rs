@@ -0,0 +1,314 @@
+//! Glyph outlines as vector paths, filled with tiny-skia.
+//!
+//! Paths are in the pixel space of the text layout, so a whole-pixel
+//! translation of the layer keeps them on the same grid as the icon.
+
+use crate::font::LoadedFont;
+use crate::spec::IconSpec;
+use crate::text::PlacedGlyph;
+use ab_glyph::{Font, OutlineCurve, Point, ScaleFont};
+use image::{Rgba, RgbaImage};
+use std::sync::Arc;
+use tiny_skia::{FillRule, LineJoin, Paint, Path, PathBuilder, Pixmap, Rect, Stroke, Transform};
+
+/// Integer pixel box covering some paths: origin and size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PixelBox {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelBox {
+    /// Smallest box containing `rect` grown by `margin` on every side.
+    pub fn around(rect: Rect, margin: f32) -> Self {
+        let (x, y) = (
+            (rect.left() - margin).floor() as i32,
+            (rect.top() - margin).floor() as i32,
+        );
+        let width = ((rect.right() + margin).ceil() as i32 - x).max(1) as u32;
+        let height = ((rect.bottom() + margin).ceil() as i32 - y).max(1) as u32;
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The box grown by `by` pixels on every side.
+    pub fn expand(self, by: u32) -> Self {
+        Self {
+            x: self.x - by as i32,
+            y: self.y - by as i32,
+            width: self.width + by * 2,
+            height: self.height + by * 2,
+        }
+    }
+}
+
+/// How paths are turned into coverage.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FillOptions {
+    pub antialias: bool,
+    /// Paths are filled at this multiple of the output resolution and
+    /// box-filtered down.
+    pub supersample: u32,
+}
+
+/// One path per glyph in pixel coordinates (y down). Glyphs without an
+/// outline (spaces) are skipped. With `snap` the stem edges are grid-fitted,
+/// see [`snap_outline`].
+pub(crate) fn glyph_paths(
+    fonts: &[Arc<LoadedFont>],
+    glyphs: &[PlacedGlyph],
+    snap: bool,
+) -> Vec<Path> {
+    glyphs
+        .iter()
+        .filter_map(|placed| {
+            let font = &fonts[placed.font].font;
+            let outline = font.outline(placed.glyph.id)?;
+            let scaled = font.as_scaled(placed.glyph.scale);
+            let (sx, sy) = (scaled.h_scale_factor(), scaled.v_scale_factor());
+            let origin = placed.glyph.position;
+            // フォント単位（y 上向き）からピクセル座標（y 下向き）へ
+            let to_px = |p: Point| Point {
+                x: origin.x + p.x * sx,
+                y: origin.y - p.y * sy,
+            };
+            let mut curves: Vec<OutlineCurve> = outline
+                .curves
+                .iter()
+                .map(|curve| match *curve {
+                    OutlineCurve::Line(a, b) => OutlineCurve::Line(to_px(a), to_px(b)),
+                    OutlineCurve::Quad(a, b, c) => OutlineCurve::Quad(to_px(a), to_px(b), to_px(c)),
+                    OutlineCurve::Cubic(a, b, c, d) => {
+                        OutlineCurve::Cubic(to_px(a), to_px(b), to_px(c), to_px(d))
+                    }
+                })
+                .collect();
+            if snap {
+                snap_outline(&mut curves);
+            }
+            curves_to_path(&curves)
+        })
+        .collect()
+}
+
+/// Build a path from a flat curve list. `ab_glyph` does not mark where a
+/// contour ends, so a curve that does not start where the last one ended
+/// begins a new contour.
+fn curves_to_path(curves: &[OutlineCurve]) -> Option<Path> {
+    let mut pb = PathBuilder::new();
+    let mut last: Option<Point> = None;
+    for curve in curves {
+        let (start, end) = match *curve {
+            OutlineCurve::Line(a, b) => (a, b),
+            OutlineCurve::Quad(a, _, c) => (a, c),
+            OutlineCurve::Cubic(a, _, _, d) => (a, d),
+        };
+        if last != Some(start) {
+            if last.is_some() {
+                pb.close();
+            }
+            pb.move_to(start.x, start.y);
+        }
+        match *curve {
+            OutlineCurve::Line(_, b) => pb.line_to(b.x, b.y),
+            OutlineCurve::Quad(_, b, c) => pb.quad_to(b.x, b.y, c.x, c.y),
+            OutlineCurve::Cubic(_, b, c, d) => pb.cubic_to(b.x, b.y, c.x, c.y, d.x, d.y),
+        }
+        last = Some(end);
+    }
+    if last.is_some() {
+        pb.close();
+    }
+    pb.finish()
+}
+
+/// Grid-fit an outline in pixel coordinates.
+///
+/// Edges of stems — straight vertical or horizontal segments, and curve
+/// extrema where the tangent is vertical or horizontal — are rounded to whole
+/// pixels. Every other point moves by interpolating between the neighbouring
+/// edges, like TrueType's IUP instruction, so the shape stays smooth while
+/// stems get crisp edges. A stem never collapses below one pixel.
+fn snap_outline(curves: &mut [OutlineCurve]) {
+    const EPS: f32 = 0.01;
+    let mut x_edges = Vec::new();
+    let mut y_edges = Vec::new();
+    for curve in curves.iter() {
+        match *curve {
+            OutlineCurve::Line(a, b) => {
+                if (a.x - b.x).abs() < EPS {
+                    x_edges.push(a.x);
+                }
+                if (a.y - b.y).abs() < EPS {
+                    y_edges.push(a.y);
+                }
+            }
+            OutlineCurve::Quad(a, c, b) => {
+                for p in [a, b] {
+                    if (p.x - c.x).abs() < EPS {
+                        x_edges.push(p.x);
+                    }
+                    if (p.y - c.y).abs() < EPS {
+                        y_edges.push(p.y);
+                    }
+                }
+            }
+            OutlineCurve::Cubic(a, c1, c2, b) => {
+                for (p, c) in [(a, c1), (b, c2)] {
+                    if (p.x - c.x).abs() < EPS {
+                        x_edges.push(p.x);
+                    }
+                    if (p.y - c.y).abs() < EPS {
+                        y_edges.push(p.y);
+                    }
+                }
+            }
+        }
+    }
+    let fit_x = GridFit::new(x_edges);
+    let fit_y = GridFit::new(y_edges);
+    let fit = |p: &mut Point| {
+        p.x = fit_x.map(p.x);
+        p.y = fit_y.map(p.y);
+    };
+    for curve in curves.iter_mut() {
+        match curve {
+            OutlineCurve::Line(a, b) => [a, b].into_iter().for_each(fit),
+            OutlineCurve::Quad(a, b, c) => [a, b, c].into_iter().for_each(fit),
+            OutlineCurve::Cubic(a, b, c, d) => [a, b, c, d].into_iter().for_each(fit),
+        }
+    }
+}
+
+/// Piecewise-linear map from original to grid-fitted coordinates along one
+/// axis.
+struct GridFit {
+    /// (original, fitted), sorted by original.
+    anchors: Vec<(f32, f32)>,
+}
+
+impl GridFit {
+    fn new(mut edges: Vec<f32>) -> Self {
+        edges.sort_by(f32::total_cmp);
+        edges.dedup_by(|a, b| (*a - *b).abs() < 0.01);
+        let mut anchors: Vec<(f32, f32)> = Vec::with_capacity(edges.len());
+        for edge in edges {
+            let mut fitted = edge.round();
+            if let Some(&(prev, prev_fitted)) = anchors.last() {
+                // 細いステムでも 1px は残し、順序も入れ替えない
+                let min_gap = if edge - prev >= 0.5 { 1.0 } else { 0.0 };
+                fitted = fitted.max(prev_fitted + min_gap);
+            }
+            anchors.push((edge, fitted));
+        }
+        Self { anchors }
+    }
+
+    fn map(&self, v: f32) -> f32 {
+        let (Some(&first), Some(&last)) = (self.anchors.first(), self.anchors.last()) else {
+            return v;
+        };
+        if v <= first.0 {
+            return v + first.1 - first.0;
+        }
+        if v >= last.0 {
+            return v + last.1 - last.0;
+        }
+        let i = self.anchors.partition_point(|&(orig, _)| orig <= v);
+        let (a, b) = (self.anchors[i - 1], self.anchors[i]);
+        a.1 + (v - a.0) * (b.1 - a.1) / (b.0 - a.0)
+    }
+}
+
+/// Box containing every path, or `None` when there is nothing to draw.
+pub(crate) fn paths_extent(paths: &[Path]) -> Option<Rect> {
+    let (min_x, min_y, max_x, max_y) = paths.iter().map(Path::bounds).fold(
+        (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+        |(x0, y0, x1, y1), b| {
+            (
+                x0.min(b.left()),
+                y0.min(b.top()),
+                x1.max(b.right()),
+                y1.max(b.bottom()),
+            )
+        },
+    );
+    if min_x > max_x || min_y > max_y {
+        return None;
+    }
+    Rect::from_ltrb(min_x, min_y, max_x, max_y)
+}
+
+/// Fill `paths` in white over the pixels of `area`, coverage in alpha.
+/// Each path is composited over the previous ones, so overlapping glyphs
+/// never cancel out.
+pub(crate) fn fill_paths(paths: &[Path], area: PixelBox, options: FillOptions) -> RgbaImage {
+    rasterize(area, options, |pixmap, paint, transform| {
+        for path in paths {
+            pixmap.fill_path(path, paint, FillRule::Winding, transform, None);
+        }
+    })
+}
+
+/// Stroke `paths` with a line `width` pixels wide centred on the outlines,
+/// white over the pixels of `area` with coverage in alpha. Joins are round so
+/// the stroke never reaches further than half its width.
+pub(crate) fn stroke_paths(
+    paths: &[Path],
+    width: f32,
+    area: PixelBox,
+    options: FillOptions,
+) -> RgbaImage {
+    let stroke = Stroke {
+        width,
+        line_join: LineJoin::Round,
+        ..Stroke::default()
+    };
+    rasterize(area, options, |pixmap, paint, transform| {
+        for path in paths {
+            pixmap.stroke_path(path, paint, &stroke, transform, None);
+        }
+    })
+}
+
+/// Run `draw` on a white paint over a supersampled pixmap of `area` and
+/// average the coverage back down to the area's resolution.
+fn rasterize(
+    area: PixelBox,
+    options: FillOptions,
+    draw: impl FnOnce(&mut Pixmap, &Paint, Transform),
+) -> RgbaImage {
+    let ss = options.supersample.clamp(1, IconSpec::MAX_SUPERSAMPLE);
+    let mut layer = RgbaImage::from_pixel(area.width, area.height, Rgba([0, 0, 0, 0]));
+    let Some(mut pixmap) = Pixmap::new(area.width * ss, area.height * ss) else {
+        return layer;
+    };
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(255, 255, 255, 255);
+    paint.anti_alias = options.antialias;
+    let transform =
+        Transform::from_translate(-area.x as f32, -area.y as f32).post_scale(ss as f32, ss as f32);
+    draw(&mut pixmap, &paint, transform);
+
+    // ss×ss のブロックを平均して出力解像度に戻す
+    let stride = (area.width * ss) as usize;
+    let alpha: Vec<u8> = pixmap.data().iter().skip(3).step_by(4).copied().collect();
+    for (x, y, pixel) in layer.enumerate_pixels_mut() {
+        let mut sum = 0u32;
+        for sy in 0..ss {
+            let row = ((y * ss + sy) as usize) * stride;
+            for sx in 0..ss {
+                sum += alpha[row + (x * ss + sx) as usize] as u32;
+            }
+        }
+        let a = (sum as f32 / (ss * ss) as f32).round() as u8;
+        *pixel = Rgba([255, 255, 255, a]);
+    }
+    layer
+}
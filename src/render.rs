@@ -0,0 +1,275 @@
+//! Rasterises an [`IconSpec`] into an RGBA image.
+
+use crate::color;
+use crate::effects::{self, Mask};
+use crate::fill::{self, FillShader};
+use crate::font::{self, LoadedFont};
+use crate::layers;
+use crate::pattern::draw_pattern;
+use crate::raster::{fill_paths, glyph_paths, paths_extent, stroke_paths, FillOptions, PixelBox};
+use crate::shape;
+use crate::spec::{Blending, IconSpec, LayerContent, Pattern, StrokeAlign};
+use crate::text::TextLayout;
+use image::RgbaImage;
+use std::sync::Arc;
+
+/// Render `spec` as a `size`×`size` image.
+///
+/// Fonts that cannot be loaded are left out of the fallback chain (the default
+/// font stands in for the primary); call [`font::resolve_chain`] first to
+/// report that as an error.
+pub fn render(spec: &IconSpec, size: u32) -> RgbaImage {
+    render_with_fonts(spec, &font::load_chain(spec), size)
+}
+
+/// Render every entry of `spec.sizes`, ready to pass to [`crate::write_ico`].
+pub fn render_sizes(spec: &IconSpec) -> Vec<(u32, RgbaImage)> {
+    spec.sizes.iter().map(|&s| (s, render(spec, s))).collect()
+}
+
+/// Render `spec` as a `size`×`size` image with an already loaded font chain:
+/// each character is drawn with the first font in `fonts` that has a glyph.
+pub fn render_with_fonts(spec: &IconSpec, fonts: &[Arc<LoadedFont>], size: u32) -> RgbaImage {
+    let text = layout_text(spec, fonts, size);
+    let mut img = RgbaImage::new(size, size);
+    for layer in spec.layers.iter().filter(|l| l.visible && l.opacity > 0.0) {
+        let source = match &layer.content {
+            LayerContent::Plate => Some(plate(spec, size)),
+            LayerContent::Pattern => pattern_source(&img, spec),
+            LayerContent::Text => text.as_ref().map(|text| draw_text(text, spec, size)),
+            LayerContent::Image { image } => layers::image_source(image, size),
+            LayerContent::Effect { effect } => text
+                .as_ref()
+                .and_then(|text| text.silhouette.as_ref())
+                .map(|silhouette| {
+                    let mut img = RgbaImage::new(size, size);
+                    let mask = effects::effect_mask(effect, silhouette, size);
+                    effects::draw(&mut img, &mask, effect, spec.blending);
+                    img
+                }),
+        };
+        if let Some(source) = source {
+            layers::composite(&mut img, &source, layer, spec.blending);
+        }
+    }
+    img
+}
+
+/// The background plate in its shape.
+fn plate(spec: &IconSpec, size: u32) -> RgbaImage {
+    let canvas = PixelBox {
+        x: 0,
+        y: 0,
+        width: size,
+        height: size,
+    };
+    let plate = fill::shader(&spec.background, size, canvas);
+    let mut img = RgbaImage::from_fn(size, size, |x, y| plate.color_at(x, y).to_rgba());
+    shape::clip_plate(&mut img, spec.bg_shape, spec.corner_radius);
+    img
+}
+
+/// The pixels of `backdrop` the pattern lights or shades, opaque; the
+/// others are transparent.
+fn pattern_source(backdrop: &RgbaImage, spec: &IconSpec) -> Option<RgbaImage> {
+    if spec.pattern == Pattern::None {
+        return None;
+    }
+    let mut img = backdrop.clone();
+    draw_pattern(&mut img, spec.pattern, &spec.pattern_params);
+    for (lit, below) in img.pixels_mut().zip(backdrop.pixels()) {
+        lit[3] = if lit.0[..3] == below.0[..3] { 0 } else { 255 };
+    }
+    Some(img)
+}
+
+/// The text rasterized and placed on the icon, ready to draw.
+struct Text {
+    glyph_layer: RgbaImage,
+    stroke_layer: Option<RgbaImage>,
+    /// Where the layers go on the icon.
+    bounds: PixelBox,
+    /// The ink of the glyphs on the icon, without the stroke.
+    text_box: PixelBox,
+    /// Shape of the glyphs and stroke, when effects, the bevel or the
+    /// extrusion need it.
+    silhouette: Option<Mask>,
+}
+
+/// Lay out and rasterize the text of `spec`; `None` when there is nothing
+/// to draw.
+fn layout_text(spec: &IconSpec, fonts: &[Arc<LoadedFont>], size: u32) -> Option<Text> {
+    if spec.text.is_empty() || fonts.is_empty() {
+        return None;
+    }
+
+    let layout = TextLayout::new(spec, fonts);
+
+    // Step 1: Measure the glyph outlines at a reference scale
+    let ref_scale = 200.0_f32;
+    let ref_paths = glyph_paths(fonts, &layout.glyphs(&spec.text, ref_scale), false);
+    let extent = paths_extent(&ref_paths)?;
+    let ref_w = extent.width().max(f32::EPSILON);
+    let ref_h = extent.height().max(f32::EPSILON);
+
+    // Step 2: Calculate scale to fill the padded inner area, leaving room
+    // for the part of the stroke outside the glyphs
+    let size_i32 = size as i32;
+    let pad_px = ((size as f32) * spec.padding).round() as i32;
+    let pad_px = pad_px.clamp(0, size_i32 / 2);
+    let inner_space = (size_i32 - pad_px * 2).max(1);
+    let stroke_px = if spec.stroke.is_none() {
+        0.0
+    } else {
+        spec.stroke.width * size as f32
+    };
+    let overhang = stroke_px * spec.stroke.align.overhang();
+    let mut target = (inner_space as f32 - overhang * 2.0).max(1.0);
+
+    // Step 3: Fill (and stroke) the glyph paths at final scale onto layers
+    // exactly as large as the ink
+    let snap = spec.snap_stems && size <= IconSpec::STEM_SNAP_MAX_SIZE;
+    let (paths, ink, span) = loop {
+        let ratio = (target / ref_w).min(target / ref_h);
+        let paths = glyph_paths(fonts, &layout.glyphs(&spec.text, ref_scale * ratio), snap);
+        let extent = paths_extent(&paths)?;
+        // 縁取りを含めて実際に色が付く画素の範囲
+        let span = PixelBox::around(extent, overhang);
+        let fits = span.width as i32 <= inner_space && span.height as i32 <= inner_space;
+        if fits || target <= 1.0 {
+            break (paths, PixelBox::around(extent, 0.0), span);
+        }
+        // 画素の境目をまたぐと 1px はみ出すので、一回り小さく合わせ直す
+        target = (target - 1.0).max(1.0);
+    };
+    let bounds = ink.expand(overhang.ceil() as u32);
+    let options = FillOptions {
+        antialias: spec.antialias,
+        supersample: spec.supersample,
+    };
+    let glyph_layer = fill_paths(&paths, bounds, options);
+    let stroke_layer = (stroke_px > 0.0).then(|| {
+        let align = spec.stroke.align;
+        // inside / outside は倍の幅で描き、片側をグリフの塗りで切り取る
+        let width = match align {
+            StrokeAlign::Center => stroke_px,
+            StrokeAlign::Inside | StrokeAlign::Outside => stroke_px * 2.0,
+        };
+        let mut layer = stroke_paths(&paths, width, bounds, options);
+        if align != StrokeAlign::Center {
+            for (stroke, glyph) in layer.pixels_mut().zip(glyph_layer.pixels()) {
+                let inside = glyph[3] as u32;
+                let keep = if align == StrokeAlign::Inside {
+                    inside
+                } else {
+                    255 - inside
+                };
+                stroke[3] = (stroke[3] as u32 * keep / 255) as u8;
+            }
+        }
+        layer
+    });
+    let (span_w, span_h) = (span.width as i32, span.height as i32);
+
+    // Step 4: Centre the inked pixels within the padded box
+    let x_margin = (inner_space - span_w).max(0);
+    let y_margin = (inner_space - span_h).max(0);
+    let mut target_x = pad_px + (inner_space - span_w) / 2;
+    let mut target_y = pad_px + (inner_space - span_h) / 2;
+    target_x = target_x.clamp(pad_px, pad_px + x_margin);
+    target_y = target_y.clamp(pad_px, pad_px + y_margin);
+
+    let (dx, dy) = (target_x - span.x, target_y - span.y);
+    let text_box = PixelBox {
+        x: ink.x + dx,
+        y: ink.y + dy,
+        ..ink
+    };
+    let bounds = PixelBox {
+        x: bounds.x + dx,
+        y: bounds.y + dy,
+        ..bounds
+    };
+
+    // Step 5: Effect layers, the bevel and the extrusion are made from the
+    // shape of the glyphs and the stroke
+    let has_effects = spec
+        .layers
+        .iter()
+        .any(|l| l.visible && matches!(l.content, LayerContent::Effect { .. }));
+    let needs_silhouette = has_effects || !spec.bevel.is_none() || !spec.extrusion.is_none();
+    let silhouette = needs_silhouette.then(|| {
+        let layers: Vec<&RgbaImage> = std::iter::once(&glyph_layer)
+            .chain(stroke_layer.as_ref())
+            .collect();
+        Mask::from_layers(size, &layers, (bounds.x, bounds.y))
+    });
+
+    Some(Text {
+        glyph_layer,
+        stroke_layer,
+        bounds,
+        text_box,
+        silhouette,
+    })
+}
+
+/// The text layer: the extrusion, then the stroke and the fill (an outside
+/// stroke goes under the fill, the others over it), then the bevel.
+fn draw_text(text: &Text, spec: &IconSpec, size: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(size, size);
+    let silhouette = text.silhouette.as_ref();
+    if let Some(silhouette) = silhouette.filter(|_| !spec.extrusion.is_none()) {
+        effects::extrude(&mut img, silhouette, &spec.extrusion, size, spec.blending);
+    }
+
+    let origin = (text.bounds.x, text.bounds.y);
+    let fill_shader = fill::shader(&spec.fill, size, text.text_box);
+    let stroke_under = spec.stroke.align == StrokeAlign::Outside;
+    let draw_stroke = |img: &mut RgbaImage| {
+        if let Some(layer) = &text.stroke_layer {
+            let shader = fill::shader(&spec.stroke.fill, size, text.bounds);
+            composite(img, layer, origin, shader.as_ref(), spec.blending);
+        }
+    };
+    if stroke_under {
+        draw_stroke(&mut img);
+    }
+    composite(
+        &mut img,
+        &text.glyph_layer,
+        origin,
+        fill_shader.as_ref(),
+        spec.blending,
+    );
+    if !stroke_under {
+        draw_stroke(&mut img);
+    }
+    if let Some(silhouette) = silhouette.filter(|_| !spec.bevel.is_none()) {
+        effects::bevel(&mut img, silhouette, &spec.bevel, size, spec.blending);
+    }
+    img
+}
+
+/// Composite `layer` (coverage in alpha) with its top-left corner at
+/// `origin`, taking colours from `shader`.
+fn composite(
+    img: &mut RgbaImage,
+    layer: &RgbaImage,
+    origin: (i32, i32),
+    shader: &dyn FillShader,
+    blending: Blending,
+) {
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    for (lx, ly, tp) in layer.enumerate_pixels() {
+        let (x, y) = (origin.0 + lx as i32, origin.1 + ly as i32);
+        if tp[3] == 0 || x < 0 || y < 0 || x >= w || y >= h {
+            continue;
+        }
+        let (x, y) = (x as u32, y as u32);
+        let paint = shader.color_at(x, y);
+        let alpha = tp[3] as f32 / 255.0 * paint.a as f32 / 255.0;
+        let blended = color::blend(*img.get_pixel(x, y), paint, alpha, blending);
+        img.put_pixel(x, y, blended);
+    }
+}
@@ -0,0 +1,90 @@
+//! Outlines of the background plate.
+
+use crate::raster::{fill_paths, FillOptions, PixelBox};
+use crate::spec::BackgroundShape;
+use image::{Rgba, RgbaImage};
+use std::f32::consts::TAU;
+use tiny_skia::{Path, PathBuilder, Rect};
+
+/// Exponent n of the squircle |x|ⁿ + |y|ⁿ = 1.
+const SQUIRCLE_EXPONENT: f32 = 5.0;
+/// Points on the squircle outline.
+const SQUIRCLE_POINTS: usize = 256;
+/// Control point distance of a cubic Bézier quarter circle of radius 1.
+const KAPPA: f32 = 0.552_284_8;
+
+/// Cut the plate in `img` to `shape`, with anti-aliased edges; the rest of
+/// the canvas becomes transparent.
+pub(crate) fn clip_plate(img: &mut RgbaImage, shape: BackgroundShape, corner_radius: f32) {
+    if shape == BackgroundShape::Square {
+        return;
+    }
+    let Some(path) = outline(shape, corner_radius, img.width() as f32) else {
+        img.pixels_mut().for_each(|p| *p = Rgba([0, 0, 0, 0]));
+        return;
+    };
+    let area = PixelBox {
+        x: 0,
+        y: 0,
+        width: img.width(),
+        height: img.height(),
+    };
+    let options = FillOptions {
+        antialias: true,
+        supersample: 1,
+    };
+    let coverage = fill_paths(&[path], area, options);
+    for (p, c) in img.pixels_mut().zip(coverage.pixels()) {
+        p[3] = (p[3] as u32 * c[3] as u32 / 255) as u8;
+    }
+}
+
+/// Outline of `shape` filling a `size`×`size` canvas, `None` for no plate.
+fn outline(shape: BackgroundShape, corner_radius: f32, size: f32) -> Option<Path> {
+    match shape {
+        BackgroundShape::Square => Some(PathBuilder::from_rect(Rect::from_xywh(
+            0.0, 0.0, size, size,
+        )?)),
+        BackgroundShape::Rounded => rounded_square(size, corner_radius.clamp(0.0, 0.5) * size),
+        BackgroundShape::Circle => PathBuilder::from_circle(size / 2.0, size / 2.0, size / 2.0),
+        BackgroundShape::Squircle => squircle(size),
+        BackgroundShape::None => None,
+    }
+}
+
+fn rounded_square(s: f32, r: f32) -> Option<Path> {
+    if r <= 0.0 {
+        return outline(BackgroundShape::Square, 0.0, s);
+    }
+    let k = r * KAPPA;
+    let mut pb = PathBuilder::new();
+    pb.move_to(r, 0.0);
+    pb.line_to(s - r, 0.0);
+    pb.cubic_to(s - r + k, 0.0, s, r - k, s, r);
+    pb.line_to(s, s - r);
+    pb.cubic_to(s, s - r + k, s - r + k, s, s - r, s);
+    pb.line_to(r, s);
+    pb.cubic_to(r - k, s, 0.0, s - r + k, 0.0, s - r);
+    pb.line_to(0.0, r);
+    pb.cubic_to(0.0, r - k, r - k, 0.0, r, 0.0);
+    pb.close();
+    pb.finish()
+}
+
+fn squircle(s: f32) -> Option<Path> {
+    let c = s / 2.0;
+    let e = 2.0 / SQUIRCLE_EXPONENT;
+    let mut pb = PathBuilder::new();
+    for i in 0..SQUIRCLE_POINTS {
+        let (sin, cos) = (i as f32 / SQUIRCLE_POINTS as f32 * TAU).sin_cos();
+        let x = c + c * cos.signum() * cos.abs().powf(e);
+        let y = c + c * sin.signum() * sin.abs().powf(e);
+        if i == 0 {
+            pb.move_to(x, y);
+        } else {
+            pb.line_to(x, y);
+        }
+    }
+    pb.close();
+    pb.finish()
+}
@@ -0,0 +1,1268 @@
+//! Plain description of an icon, independent of any UI toolkit.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// An 8-bit sRGB colour with straight alpha.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn to_rgba(self) -> image::Rgba<u8> {
+        image::Rgba([self.r, self.g, self.b, self.a])
+    }
+}
+
+/// Error returned when a string is not a `#RRGGBB` / `#RRGGBBAA` colour.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseColorError(String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid colour {:?} (expected #RRGGBB or #RRGGBBAA)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parses `#RRGGBB` or `#RRGGBBAA`; the leading `#` is optional.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseColorError(s.to_string());
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+            return Err(err());
+        }
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err());
+        let a = if hex.len() == 8 { byte(6)? } else { 255 };
+        Ok(Self::rgba(byte(0)?, byte(2)?, byte(4)?, a))
+    }
+}
+
+impl fmt::Display for Color {
+    /// Formats as `#RRGGBB`, or `#RRGGBBAA` when not fully opaque.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.r, self.g, self.b)?;
+        if self.a != 255 {
+            write!(f, "{:02X}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Decoration drawn over the background colour.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pattern {
+    None,
+    #[default]
+    Chevron,
+    Stripes,
+    Dots,
+    Grid,
+    Hexagons,
+    Waves,
+    Checkerboard,
+    /// Random grain.
+    Noise,
+    /// Concentric circles around the centre of the icon.
+    Rings,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 10] = [
+        Pattern::None,
+        Pattern::Chevron,
+        Pattern::Stripes,
+        Pattern::Dots,
+        Pattern::Grid,
+        Pattern::Hexagons,
+        Pattern::Waves,
+        Pattern::Checkerboard,
+        Pattern::Noise,
+        Pattern::Rings,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pattern::None => "none",
+            Pattern::Chevron => "chevron",
+            Pattern::Stripes => "stripes",
+            Pattern::Dots => "dots",
+            Pattern::Grid => "grid",
+            Pattern::Hexagons => "hexagons",
+            Pattern::Waves => "waves",
+            Pattern::Checkerboard => "checkerboard",
+            Pattern::Noise => "noise",
+            Pattern::Rings => "rings",
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown pattern {s:?}"))
+    }
+}
+
+/// Which font to draw the text with.
+///
+/// The default (every field empty) is the bundled Aoboshi One. A `path` takes
+/// precedence over `family`/`style`, which name an installed font.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FontSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+    /// Style within `family`, e.g. "Bold". `None` prefers the regular style.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    /// Font file supplied by the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Face within `path` when it is a collection (`.ttc`/`.otc`).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub index: u32,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+impl FontSpec {
+    pub fn family(family: impl Into<String>, style: Option<&str>) -> Self {
+        Self {
+            family: Some(family.into()),
+            style: style.map(str::to_string),
+            path: None,
+            index: 0,
+        }
+    }
+
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::file_face(path, 0)
+    }
+
+    /// Face `index` of a font collection file.
+    pub fn file_face(path: impl Into<PathBuf>, index: u32) -> Self {
+        Self {
+            path: Some(path.into()),
+            index,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_bundled(&self) -> bool {
+        self.family.is_none() && self.path.is_none()
+    }
+}
+
+impl fmt::Display for FontSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            let name = path.file_name().unwrap_or(path.as_os_str());
+            write!(f, "{}", Path::new(name).display())?;
+            match self.index {
+                0 => Ok(()),
+                i => write!(f, " #{i}"),
+            }
+        } else if let Some(family) = &self.family {
+            write!(f, "{family}")?;
+            match &self.style {
+                Some(style) => write!(f, " {style}"),
+                None => Ok(()),
+            }
+        } else {
+            write!(f, "Aoboshi One (bundled)")
+        }
+    }
+}
+
+/// Outline of the background plate; the canvas outside it is transparent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundShape {
+    /// The whole canvas.
+    #[default]
+    Square,
+    /// Square with circular corners of [`IconSpec::corner_radius`].
+    Rounded,
+    Circle,
+    /// Superellipse, the soft square of app icons.
+    Squircle,
+    /// No plate: only the text on a transparent canvas.
+    None,
+}
+
+impl BackgroundShape {
+    pub const ALL: [BackgroundShape; 5] = [
+        BackgroundShape::Square,
+        BackgroundShape::Rounded,
+        BackgroundShape::Circle,
+        BackgroundShape::Squircle,
+        BackgroundShape::None,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BackgroundShape::Square => "square",
+            BackgroundShape::Rounded => "rounded",
+            BackgroundShape::Circle => "circle",
+            BackgroundShape::Squircle => "squircle",
+            BackgroundShape::None => "none",
+        }
+    }
+}
+
+impl FromStr for BackgroundShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|b| b.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown background shape {s:?}"))
+    }
+}
+
+/// Alignment of the lines of a multi-line text block. In vertical text,
+/// `Left` and `Right` align the columns to the top and bottom.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    pub const ALL: [TextAlign; 3] = [TextAlign::Left, TextAlign::Center, TextAlign::Right];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TextAlign::Left => "left",
+            TextAlign::Center => "center",
+            TextAlign::Right => "right",
+        }
+    }
+}
+
+impl FromStr for TextAlign {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|a| a.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown alignment {s:?}"))
+    }
+}
+
+/// Direction the text runs in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WritingMode {
+    #[default]
+    Horizontal,
+    /// 縦書き: characters stack top to bottom, columns run right to left.
+    Vertical,
+}
+
+impl WritingMode {
+    pub const ALL: [WritingMode; 2] = [WritingMode::Horizontal, WritingMode::Vertical];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WritingMode::Horizontal => "horizontal",
+            WritingMode::Vertical => "vertical",
+        }
+    }
+}
+
+impl FromStr for WritingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown writing mode {s:?}"))
+    }
+}
+
+/// How anti-aliased text is composited over the background.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Blending {
+    /// Mix the sRGB bytes directly (the original look).
+    #[default]
+    Gamma,
+    /// Mix in linear light: edges keep their weight and do not darken.
+    Linear,
+}
+
+impl Blending {
+    pub const ALL: [Blending; 2] = [Blending::Gamma, Blending::Linear];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Blending::Gamma => "gamma",
+            Blending::Linear => "linear",
+        }
+    }
+}
+
+impl FromStr for Blending {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|b| b.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown blending {s:?}"))
+    }
+}
+
+/// Colour space gradients are interpolated in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GradientSpace {
+    /// Interpolate sRGB bytes (the original look).
+    #[default]
+    Srgb,
+    /// Interpolate linear-light RGB.
+    Linear,
+    /// Interpolate in OKLab: perceptually even, no grey midpoints.
+    Oklab,
+}
+
+impl GradientSpace {
+    pub const ALL: [GradientSpace; 3] = [
+        GradientSpace::Srgb,
+        GradientSpace::Linear,
+        GradientSpace::Oklab,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GradientSpace::Srgb => "srgb",
+            GradientSpace::Linear => "linear",
+            GradientSpace::Oklab => "oklab",
+        }
+    }
+}
+
+impl FromStr for GradientSpace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|g| g.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown gradient space {s:?}"))
+    }
+}
+
+/// Shape of a gradient.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GradientKind {
+    /// Along a line through the centre at [`Gradient::angle`].
+    #[default]
+    Linear,
+    /// Outwards from the centre to the farthest corner.
+    Radial,
+    /// Clockwise around the centre, starting at [`Gradient::angle`].
+    Conic,
+}
+
+impl GradientKind {
+    pub const ALL: [GradientKind; 3] = [
+        GradientKind::Linear,
+        GradientKind::Radial,
+        GradientKind::Conic,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GradientKind::Linear => "linear",
+            GradientKind::Radial => "radial",
+            GradientKind::Conic => "conic",
+        }
+    }
+}
+
+impl FromStr for GradientKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|k| k.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown gradient type {s:?}"))
+    }
+}
+
+/// A colour at a position along a gradient.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// 0.0 (start) ..= 1.0 (end).
+    pub offset: f32,
+    pub color: Color,
+}
+
+impl GradientStop {
+    pub const fn new(offset: f32, color: Color) -> Self {
+        Self { offset, color }
+    }
+}
+
+/// A colour gradient: its shape, direction and colour stops.
+///
+/// Missing fields take their [`Default`] value when deserialized.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    /// Degrees clockwise from "towards the top", like CSS: 90 runs left to
+    /// right, 45 is the bottom-left to top-right diagonal.
+    pub angle: f32,
+    pub space: GradientSpace,
+    /// Stops in any order; colours before the first and after the last stop
+    /// are held.
+    pub stops: Vec<GradientStop>,
+}
+
+impl Gradient {
+    /// A linear gradient between two colours at `angle`.
+    pub fn two_color(start: Color, end: Color, angle: f32) -> Self {
+        Self {
+            kind: GradientKind::Linear,
+            angle,
+            space: GradientSpace::Srgb,
+            stops: vec![GradientStop::new(0.0, start), GradientStop::new(1.0, end)],
+        }
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self::two_color(Color::rgb(120, 90, 220), Color::rgb(20, 170, 130), 45.0)
+    }
+}
+
+/// How the background [`Pattern`] is drawn.
+///
+/// Lengths are in pixels of a [`PatternParams::REFERENCE_SIZE`] icon and
+/// scale with the icon, so that every ICO entry shows the same pattern.
+/// Missing fields take their [`Default`] value when deserialized.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatternParams {
+    /// Lines per pixel relative to the pattern's own spacing; 2.0 packs
+    /// them twice as close.
+    pub density: f32,
+    /// Rotation about the centre, degrees clockwise.
+    pub angle: f32,
+    pub line_width: f32,
+    /// How much the lines lighten the background, in 8-bit levels.
+    pub highlight: u8,
+    /// How much the pixels under the lines are darkened, in 8-bit levels.
+    pub shadow: u8,
+    /// Icons smaller than this many pixels draw the pattern at the scale it
+    /// has at this size, with fewer lines rather than lines too fine to see.
+    pub min_size: u32,
+    /// Leave the pattern out below `min_size` instead.
+    pub hide_small: bool,
+}
+
+impl PatternParams {
+    /// Icon size at which pattern lengths are in whole pixels.
+    pub const REFERENCE_SIZE: u32 = 256;
+}
+
+impl Default for PatternParams {
+    fn default() -> Self {
+        Self {
+            density: 1.0,
+            angle: 0.0,
+            line_width: 1.0,
+            highlight: 10,
+            shadow: 6,
+            min_size: 128,
+            hide_small: false,
+        }
+    }
+}
+
+/// A tiled pattern used as a fill: the pattern's lines in `color` over
+/// `background`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatternFill {
+    pub pattern: Pattern,
+    pub color: Color,
+    pub background: Color,
+    /// Size of the pattern relative to its default cell size.
+    pub scale: f32,
+}
+
+impl Default for PatternFill {
+    fn default() -> Self {
+        Self {
+            pattern: Pattern::Chevron,
+            color: Color::rgb(255, 255, 255),
+            background: Color::rgb(120, 90, 220),
+            scale: 1.0,
+        }
+    }
+}
+
+/// An image file (PNG, JPEG) clipped to the glyph shapes. It is scaled to
+/// cover the text's bounding box.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageFill {
+    pub path: PathBuf,
+}
+
+/// Four corner colours blended bilinearly across the icon: the soft
+/// multi-colour backdrop of modern app icons.
+///
+/// Missing fields take their [`Default`] value when deserialized.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeshGradient {
+    /// Top-left, top-right, bottom-right, bottom-left.
+    pub corners: [Color; 4],
+    pub space: GradientSpace,
+}
+
+impl Default for MeshGradient {
+    fn default() -> Self {
+        Self {
+            corners: [
+                Color::rgb(255, 154, 158),
+                Color::rgb(250, 208, 196),
+                Color::rgb(161, 140, 209),
+                Color::rgb(251, 194, 235),
+            ],
+            space: GradientSpace::Oklab,
+        }
+    }
+}
+
+/// What the glyphs, or the background plate, are filled with.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Fill {
+    Solid { color: Color },
+    Gradient(Gradient),
+    Pattern(PatternFill),
+    Image(ImageFill),
+    Mesh(MeshGradient),
+}
+
+impl Fill {
+    /// Names of the fill types, as written in project files.
+    pub const KINDS: [&'static str; 5] = ["solid", "gradient", "mesh", "pattern", "image"];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Fill::Solid { .. } => "solid",
+            Fill::Gradient(_) => "gradient",
+            Fill::Pattern(_) => "pattern",
+            Fill::Image(_) => "image",
+            Fill::Mesh(_) => "mesh",
+        }
+    }
+
+    /// The gradient of a gradient fill; any other fill is replaced by the
+    /// default gradient first.
+    pub fn gradient_mut(&mut self) -> &mut Gradient {
+        if !matches!(self, Fill::Gradient(_)) {
+            *self = Fill::Gradient(Gradient::default());
+        }
+        match self {
+            Fill::Gradient(gradient) => gradient,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The mesh of a mesh fill; any other fill is replaced by the default
+    /// mesh first.
+    pub fn mesh_mut(&mut self) -> &mut MeshGradient {
+        if !matches!(self, Fill::Mesh(_)) {
+            *self = Fill::Mesh(MeshGradient::default());
+        }
+        match self {
+            Fill::Mesh(mesh) => mesh,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The pattern of a pattern fill; any other fill is replaced by the
+    /// default pattern fill first.
+    pub fn pattern_mut(&mut self) -> &mut PatternFill {
+        if !matches!(self, Fill::Pattern(_)) {
+            *self = Fill::Pattern(PatternFill::default());
+        }
+        match self {
+            Fill::Pattern(pattern) => pattern,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Fill::Gradient(Gradient::default())
+    }
+}
+
+/// Where a stroke sits relative to the glyph outline.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrokeAlign {
+    /// Inside the glyphs, over the fill; the glyphs keep their size.
+    Inside,
+    /// Centred on the outline.
+    Center,
+    /// Around the glyphs, under the fill.
+    #[default]
+    Outside,
+}
+
+impl StrokeAlign {
+    pub const ALL: [StrokeAlign; 3] = [
+        StrokeAlign::Inside,
+        StrokeAlign::Center,
+        StrokeAlign::Outside,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StrokeAlign::Inside => "inside",
+            StrokeAlign::Center => "center",
+            StrokeAlign::Outside => "outside",
+        }
+    }
+
+    /// How far the stroke reaches beyond the outline, as a multiple of its
+    /// width.
+    pub fn overhang(self) -> f32 {
+        match self {
+            StrokeAlign::Inside => 0.0,
+            StrokeAlign::Center => 0.5,
+            StrokeAlign::Outside => 1.0,
+        }
+    }
+}
+
+impl FromStr for StrokeAlign {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|a| a.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown stroke alignment {s:?}"))
+    }
+}
+
+/// Outline around the glyphs.
+///
+/// Missing fields take their [`Default`] value when deserialized.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stroke {
+    /// Fraction of the icon size; 0 means no stroke.
+    pub width: f32,
+    pub align: StrokeAlign,
+    pub fill: Fill,
+}
+
+impl Stroke {
+    pub fn is_none(&self) -> bool {
+        self.width <= 0.0
+    }
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self {
+            width: 0.0,
+            align: StrokeAlign::Outside,
+            fill: Fill::Solid {
+                color: Color::rgb(48, 38, 80),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EffectKind {
+    /// Blurred copy of the text under it.
+    #[default]
+    DropShadow,
+    /// Shadow cast inside the glyphs by their edges, over the fill.
+    InnerShadow,
+    /// Blurred halo around the text, usually in a light colour.
+    OuterGlow,
+    /// Flat-design shadow: the text smeared along `offset`.
+    LongShadow,
+}
+
+impl EffectKind {
+    pub const ALL: [EffectKind; 4] = [
+        EffectKind::DropShadow,
+        EffectKind::InnerShadow,
+        EffectKind::OuterGlow,
+        EffectKind::LongShadow,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EffectKind::DropShadow => "drop-shadow",
+            EffectKind::InnerShadow => "inner-shadow",
+            EffectKind::OuterGlow => "outer-glow",
+            EffectKind::LongShadow => "long-shadow",
+        }
+    }
+}
+
+impl FromStr for EffectKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|k| k.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown effect {s:?}"))
+    }
+}
+
+/// Shadow or glow drawn from the shape of the text (including its stroke).
+///
+/// Lengths are fractions of the icon size, so that every ICO entry looks
+/// the same. Missing fields take the defaults of a drop shadow when
+/// deserialized.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Effect {
+    pub kind: EffectKind,
+    /// Shift right and down; for a long shadow, its direction and length.
+    pub offset: [f32; 2],
+    /// Blur radius.
+    pub blur: f32,
+    /// Grows the shape before blurring.
+    pub spread: f32,
+    pub color: Color,
+    /// 0.0..=1.0
+    pub opacity: f32,
+}
+
+impl Effect {
+    /// Largest offset either way; a long shadow may reach across the icon.
+    pub const MAX_OFFSET: f32 = 1.0;
+    /// Largest blur radius.
+    pub const MAX_BLUR: f32 = 0.2;
+    /// Largest spread.
+    pub const MAX_SPREAD: f32 = 0.1;
+
+    /// An effect of `kind` with settings that suit it.
+    pub fn new(kind: EffectKind) -> Self {
+        let black = Color::rgb(0, 0, 0);
+        let (offset, blur, spread, color, opacity) = match kind {
+            EffectKind::DropShadow => ([0.02, 0.03], 0.03, 0.0, black, 0.5),
+            EffectKind::InnerShadow => ([0.01, 0.015], 0.015, 0.0, black, 0.4),
+            EffectKind::OuterGlow => ([0.0, 0.0], 0.05, 0.01, Color::rgb(255, 240, 180), 0.8),
+            EffectKind::LongShadow => ([0.5, 0.5], 0.0, 0.0, black, 0.25),
+        };
+        Self {
+            kind,
+            offset,
+            blur,
+            spread,
+            color,
+            opacity,
+        }
+    }
+}
+
+impl Default for Effect {
+    fn default() -> Self {
+        Self::new(EffectKind::DropShadow)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BevelStyle {
+    /// The glyphs are bevelled inwards from their outline.
+    #[default]
+    Inner,
+    /// The outline is raised: shading on both sides of it.
+    Emboss,
+}
+
+impl BevelStyle {
+    pub const ALL: [BevelStyle; 2] = [BevelStyle::Inner, BevelStyle::Emboss];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BevelStyle::Inner => "inner",
+            BevelStyle::Emboss => "emboss",
+        }
+    }
+}
+
+impl FromStr for BevelStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|b| b.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown bevel style {s:?}"))
+    }
+}
+
+/// Lit bevel on the text, shaded from a distance field of its shape.
+///
+/// Missing fields take their [`Default`] value when deserialized.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bevel {
+    pub style: BevelStyle,
+    /// Width of the bevelled rim as a fraction of the icon size; 0 means no
+    /// bevel.
+    pub size: f32,
+    /// Strength of the shading, 0.0..=1.0.
+    pub depth: f32,
+    /// Direction the light comes from, degrees clockwise from up.
+    pub angle: f32,
+    /// Rounds off the bevel, as a fraction of the icon size.
+    pub soften: f32,
+    pub highlight: Color,
+    pub shadow: Color,
+}
+
+impl Bevel {
+    pub fn is_none(&self) -> bool {
+        self.size <= 0.0
+    }
+}
+
+impl Default for Bevel {
+    fn default() -> Self {
+        Self {
+            style: BevelStyle::Inner,
+            size: 0.0,
+            depth: 0.6,
+            angle: 315.0,
+            soften: 0.0,
+            highlight: Color::rgb(255, 255, 255),
+            shadow: Color::rgb(0, 0, 0),
+        }
+    }
+}
+
+/// Solid 3D sides: copies of the text stacked behind it.
+///
+/// Missing fields take their [`Default`] value when deserialized.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Extrusion {
+    /// Length as a fraction of the icon size; 0 means no extrusion.
+    pub depth: f32,
+    /// Direction the sides go, degrees clockwise from up.
+    pub angle: f32,
+    pub color: Color,
+    /// How much darker the far end is, 0.0..=1.0.
+    pub shade: f32,
+}
+
+impl Extrusion {
+    pub fn is_none(&self) -> bool {
+        self.depth <= 0.0
+    }
+}
+
+impl Default for Extrusion {
+    fn default() -> Self {
+        Self {
+            depth: 0.0,
+            angle: 135.0,
+            color: Color::rgb(48, 38, 80),
+            shade: 0.3,
+        }
+    }
+}
+
+/// How a layer's colours combine with the pixels under it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlendMode {
+    /// Paint over what is underneath.
+    #[default]
+    Normal,
+    /// Darken: white is transparent, black stays black.
+    Multiply,
+    /// Lighten: black is transparent, white stays white.
+    Screen,
+    /// Multiply the shadows and screen the highlights of what is underneath.
+    Overlay,
+    /// A gentler overlay, like shining a diffuse light.
+    SoftLight,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 5] = [
+        BlendMode::Normal,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Overlay,
+        BlendMode::SoftLight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BlendMode::Normal => "normal",
+            BlendMode::Multiply => "multiply",
+            BlendMode::Screen => "screen",
+            BlendMode::Overlay => "overlay",
+            BlendMode::SoftLight => "soft-light",
+        }
+    }
+}
+
+impl FromStr for BlendMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown blend mode {s:?}"))
+    }
+}
+
+/// How an image layer is sized to the icon before [`ImageLayer::scale`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFit {
+    /// Fill the icon, cropping the longer side.
+    #[default]
+    Cover,
+    /// Fit inside the icon, leaving the shorter side empty.
+    Contain,
+    /// Fill the icon, ignoring the aspect ratio.
+    Stretch,
+}
+
+impl ImageFit {
+    pub const ALL: [ImageFit; 3] = [ImageFit::Cover, ImageFit::Contain, ImageFit::Stretch];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageFit::Cover => "cover",
+            ImageFit::Contain => "contain",
+            ImageFit::Stretch => "stretch",
+        }
+    }
+}
+
+impl FromStr for ImageFit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|f| f.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown image fit {s:?}"))
+    }
+}
+
+/// An image file (PNG, JPEG) such as a logo or photo: the content of an
+/// image [`Layer`].
+///
+/// It is resampled from the file for every icon size. Missing fields take
+/// their [`Default`] value when deserialized.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageLayer {
+    pub path: PathBuf,
+    pub fit: ImageFit,
+    /// Shift of the centre right and down, as fractions of the icon size.
+    pub offset: [f32; 2],
+    /// Size relative to the fitted size.
+    pub scale: f32,
+}
+
+impl Default for ImageLayer {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::new(),
+            fit: ImageFit::Cover,
+            offset: [0.0, 0.0],
+            scale: 1.0,
+        }
+    }
+}
+
+/// What a [`Layer`] draws. The plate, pattern and text take their settings
+/// from the [`IconSpec`]; images and effects carry their own.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LayerContent {
+    /// The background plate: [`IconSpec::background`] in the shape of
+    /// [`IconSpec::bg_shape`].
+    Plate,
+    /// [`IconSpec::pattern`], lighting the layers below it.
+    Pattern,
+    /// The text with its stroke, bevel and extrusion.
+    Text,
+    Image {
+        image: ImageLayer,
+    },
+    /// A shadow or glow made from the shape of the text.
+    Effect {
+        effect: Effect,
+    },
+}
+
+impl LayerContent {
+    /// Names of the layer types, as written in project files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LayerContent::Plate => "plate",
+            LayerContent::Pattern => "pattern",
+            LayerContent::Text => "text",
+            LayerContent::Image { .. } => "image",
+            LayerContent::Effect { .. } => "effect",
+        }
+    }
+}
+
+/// One entry of the layer stack, composited over the layers below it.
+///
+/// Missing fields deserialize as a visible, opaque, unclipped layer with
+/// the normal blend mode.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    #[serde(flatten)]
+    pub content: LayerContent,
+    #[serde(default = "default_true")]
+    pub visible: bool,
+    /// 0.0..=1.0
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    #[serde(default)]
+    pub blend: BlendMode,
+    /// Paint only where the layers below are (a clipping mask), keeping
+    /// their transparency.
+    #[serde(default)]
+    pub clip: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+impl Layer {
+    /// A visible, opaque layer of `content`. The pattern is clipped to what
+    /// is below it, so that it stays on the plate.
+    pub fn new(content: LayerContent) -> Self {
+        Self {
+            clip: content == LayerContent::Pattern,
+            content,
+            visible: true,
+            opacity: 1.0,
+            blend: BlendMode::Normal,
+        }
+    }
+
+    /// The plate, its pattern and the text: the icon before any layers are
+    /// added.
+    pub fn default_stack() -> Vec<Layer> {
+        [
+            LayerContent::Plate,
+            LayerContent::Pattern,
+            LayerContent::Text,
+        ]
+        .into_iter()
+        .map(Layer::new)
+        .collect()
+    }
+
+    /// Layers the stack always has; they can be hidden but not removed.
+    pub fn is_builtin(&self) -> bool {
+        matches!(
+            self.content,
+            LayerContent::Plate | LayerContent::Pattern | LayerContent::Text
+        )
+    }
+}
+
+/// OpenType feature tags are exactly four printable ASCII characters.
+pub fn is_feature_tag(tag: &str) -> bool {
+    tag.len() == 4 && tag.bytes().all(|b| b.is_ascii_graphic() || b == b' ')
+}
+
+/// Everything needed to render an icon.
+///
+/// Missing fields take their [`Default`] value when deserialized.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IconSpec {
+    /// One or more lines separated by `\n`.
+    pub text: String,
+    pub writing_mode: WritingMode,
+    pub text_align: TextAlign,
+    /// Distance between baselines (columns, in vertical text) as a multiple
+    /// of the font's line spacing.
+    pub line_height: f32,
+    #[serde(skip_serializing_if = "FontSpec::is_bundled")]
+    pub font: FontSpec,
+    /// Fonts tried in order for characters `font` has no glyph for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fallback_fonts: Vec<FontSpec>,
+    /// OpenType feature toggles by tag, e.g. `liga = false`, `ss01 = true`.
+    /// Features not listed keep the font's defaults.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub font_features: BTreeMap<String, bool>,
+    /// Fill of the background plate.
+    pub background: Fill,
+    pub bg_shape: BackgroundShape,
+    /// Corner radius of [`BackgroundShape::Rounded`], 0.0..=0.5 of the icon
+    /// size.
+    pub corner_radius: f32,
+    /// Fill of the text.
+    pub fill: Fill,
+    #[serde(skip_serializing_if = "Stroke::is_none")]
+    pub stroke: Stroke,
+    #[serde(skip_serializing_if = "Bevel::is_none")]
+    pub bevel: Bevel,
+    #[serde(skip_serializing_if = "Extrusion::is_none")]
+    pub extrusion: Extrusion,
+    pub blending: Blending,
+    /// 0.0..=0.4 — fraction of canvas used as margin
+    pub padding: f32,
+    pub pattern: Pattern,
+    pub pattern_params: PatternParams,
+    /// Anti-alias glyph edges; off gives hard 1-bit edges.
+    pub antialias: bool,
+    /// Glyphs are rasterized at this multiple of the icon size and averaged
+    /// down, 1 (off) to [`IconSpec::MAX_SUPERSAMPLE`].
+    pub supersample: u32,
+    /// Snap stem edges to the pixel grid at sizes up to
+    /// [`IconSpec::STEM_SNAP_MAX_SIZE`], for crisp 16×16 and 24×24 icons.
+    pub snap_stems: bool,
+    /// Sizes embedded in an ICO file.
+    pub sizes: Vec<u32>,
+    /// What is drawn, bottom first.
+    pub layers: Vec<Layer>,
+}
+
+impl IconSpec {
+    pub const DEFAULT_SIZES: [u32; 9] = [16, 24, 32, 48, 64, 72, 96, 128, 256];
+    pub const MAX_SUPERSAMPLE: u32 = 8;
+    /// Largest icon size [`IconSpec::snap_stems`] applies to; above it the
+    /// stems are wide enough that snapping would only distort the shapes.
+    pub const STEM_SNAP_MAX_SIZE: u32 = 48;
+
+    /// Insert `layer` just below the text layer, or just above it, and
+    /// return its index. Without a text layer it goes on top.
+    pub fn insert_layer(&mut self, layer: Layer, above_text: bool) -> usize {
+        let index = match self
+            .layers
+            .iter()
+            .position(|l| l.content == LayerContent::Text)
+        {
+            Some(text) if above_text => text + 1,
+            Some(text) => text,
+            None => self.layers.len(),
+        };
+        self.layers.insert(index, layer);
+        index
+    }
+}
+
+impl Default for IconSpec {
+    fn default() -> Self {
+        Self {
+            text: "G".to_string(),
+            writing_mode: WritingMode::Horizontal,
+            text_align: TextAlign::Center,
+            line_height: 1.0,
+            font: FontSpec::default(),
+            fallback_fonts: Vec::new(),
+            font_features: BTreeMap::new(),
+            background: Fill::Solid {
+                color: Color::rgb(242, 220, 198),
+            },
+            bg_shape: BackgroundShape::Square,
+            corner_radius: 0.2,
+            fill: Fill::default(),
+            stroke: Stroke::default(),
+            bevel: Bevel::default(),
+            extrusion: Extrusion::default(),
+            blending: Blending::Gamma,
+            padding: 0.1,
+            pattern: Pattern::Chevron,
+            pattern_params: PatternParams::default(),
+            antialias: true,
+            supersample: 1,
+            snap_stems: false,
+            sizes: Self::DEFAULT_SIZES.to_vec(),
+            layers: Layer::default_stack(),
+        }
+    }
+}
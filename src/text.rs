@@ -0,0 +1,236 @@
+//! Text layout: splits text into runs per fallback font and shapes each run.
+
+use crate::font::LoadedFont;
+use crate::spec::{is_feature_tag, IconSpec, TextAlign, WritingMode};
+use ab_glyph::{point, Font, Glyph, GlyphId, PxScale, ScaleFont};
+use rustybuzz::{Direction, Feature, UnicodeBuffer};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
+
+/// A glyph positioned on the baseline, with the index of the font it came from.
+pub(crate) struct PlacedGlyph {
+    pub font: usize,
+    pub glyph: Glyph,
+}
+
+/// Everything that decides where glyphs go, independent of the scale.
+pub(crate) struct TextLayout<'a> {
+    pub fonts: &'a [Arc<LoadedFont>],
+    pub features: Vec<Feature>,
+    pub mode: WritingMode,
+    pub align: TextAlign,
+    pub line_height: f32,
+}
+
+impl<'a> TextLayout<'a> {
+    pub fn new(spec: &IconSpec, fonts: &'a [Arc<LoadedFont>]) -> Self {
+        Self {
+            fonts,
+            features: shaping_features(spec.writing_mode, &spec.font_features),
+            mode: spec.writing_mode,
+            align: spec.text_align,
+            line_height: spec.line_height,
+        }
+    }
+
+    /// Lay out every line of `text` at `scale`. Lines are `line_height` times
+    /// the primary font's line spacing apart and aligned against the widest
+    /// line; the first baseline sits at the tallest ascent, so the block starts
+    /// near y = 0.
+    ///
+    /// In vertical mode each line becomes a column: columns are the same
+    /// distance apart, the first one on the right, and `align` moves them
+    /// along their length (left = top).
+    pub fn glyphs(&self, text: &str, scale: f32) -> Vec<PlacedGlyph> {
+        let primary = self.fonts[0].font.as_scaled(PxScale::from(scale));
+        let line_advance = (primary.height() + primary.line_gap()) * self.line_height;
+
+        let lines: Vec<(Vec<PlacedGlyph>, f32)> = text
+            .lines()
+            .map(|line| match self.mode {
+                WritingMode::Horizontal => layout_line(self.fonts, line, scale, &self.features),
+                WritingMode::Vertical => layout_column(self.fonts, line, scale, &self.features),
+            })
+            .collect();
+        let block_len = lines.iter().map(|(_, w)| *w).fold(0.0_f32, f32::max);
+
+        let columns = lines.len();
+        let mut glyphs = Vec::new();
+        for (i, (line, len)) in lines.into_iter().enumerate() {
+            let shift = match self.align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => (block_len - len) / 2.0,
+                TextAlign::Right => block_len - len,
+            };
+            let (dx, dy) = match self.mode {
+                WritingMode::Horizontal => (shift, i as f32 * line_advance),
+                // 縦書きは右の列から始まる。列の中心線が x = 0 なので半列分ずらす
+                WritingMode::Vertical => (
+                    (columns - 1 - i) as f32 * line_advance + primary.height() / 2.0,
+                    shift,
+                ),
+            };
+            glyphs.extend(line.into_iter().map(|mut placed| {
+                placed.glyph.position.x += dx;
+                placed.glyph.position.y += dy;
+                placed
+            }));
+        }
+        glyphs
+    }
+}
+
+/// Convert the spec's feature toggles (`liga = false`, `ss01 = true`) into
+/// shaper features applied to the whole run. Tags that are not 4 ASCII
+/// characters are ignored.
+///
+/// Vertical text also turns on the vertical alternates (`vert`, `vrt2`) so
+/// that punctuation and the long vowel mark rotate; a toggle in the spec
+/// overrides them because later features win.
+fn shaping_features(mode: WritingMode, toggles: &BTreeMap<String, bool>) -> Vec<Feature> {
+    let vertical: &[&str] = match mode {
+        WritingMode::Horizontal => &[],
+        WritingMode::Vertical => &["vert", "vrt2"],
+    };
+    let tag = |t: &str| rustybuzz::ttf_parser::Tag::from_bytes_lossy(t.as_bytes());
+    vertical
+        .iter()
+        .map(|t| Feature::new(tag(t), 1, ..))
+        .chain(
+            toggles
+                .iter()
+                .filter(|(t, _)| is_feature_tag(t))
+                .map(|(t, &on)| Feature::new(tag(t), on as u32, ..)),
+        )
+        .collect()
+}
+
+/// Index of the first font in `fonts` that has a glyph for `c`. Falls back to
+/// the primary font (which draws its `.notdef`) when none does.
+fn font_for_char(fonts: &[Arc<LoadedFont>], c: char) -> usize {
+    fonts
+        .iter()
+        .position(|f| f.font.glyph_id(c) != GlyphId(0))
+        .unwrap_or(0)
+}
+
+/// Characters that must be shaped together with the character before them.
+fn joins_previous(c: char) -> bool {
+    c.general_category_group() == GeneralCategoryGroup::Mark
+        || matches!(c, '\u{200C}' | '\u{200D}' | '\u{FE00}'..='\u{FE0F}')
+}
+
+/// Split `text` into runs that each use one font. Combining marks and joiners
+/// stay in the run of their base character when that font can draw them, so
+/// the shaper sees whole clusters.
+fn font_runs<'t>(fonts: &[Arc<LoadedFont>], text: &'t str) -> Vec<(usize, &'t str)> {
+    let mut runs: Vec<(usize, usize, usize)> = Vec::new(); // (font, start, end)
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        if let Some(last) = runs.last_mut() {
+            let keep = joins_previous(c) && fonts[last.0].font.glyph_id(c) != GlyphId(0);
+            if keep || font_for_char(fonts, c) == last.0 {
+                last.2 = end;
+                continue;
+            }
+        }
+        runs.push((font_for_char(fonts, c), i, end));
+    }
+    runs.into_iter()
+        .map(|(font, start, end)| (font, &text[start..end]))
+        .collect()
+}
+
+/// Lay `text` out on one line: each font run is shaped (kerning, ligatures,
+/// complex scripts) and the runs are placed one after another.
+/// Returns the glyphs and the line's advance width.
+///
+/// The baseline sits at the tallest ascent among the fonts actually used, so
+/// the run starts at y = 0 like a single-font run would.
+fn layout_line(
+    fonts: &[Arc<LoadedFont>],
+    text: &str,
+    scale: f32,
+    features: &[Feature],
+) -> (Vec<PlacedGlyph>, f32) {
+    let scale = PxScale::from(scale);
+    let runs = font_runs(fonts, text);
+
+    let ascent = runs
+        .iter()
+        .map(|&(i, _)| fonts[i].font.as_scaled(scale).ascent())
+        .fold(0.0_f32, f32::max);
+
+    let mut x = 0.0_f32;
+    let mut glyphs = Vec::new();
+    for (i, run) in runs {
+        let font = fonts[i].font.as_scaled(scale);
+        let Some(face) = rustybuzz::Face::from_slice(fonts[i].data(), fonts[i].index) else {
+            continue;
+        };
+        // 形状化結果はフォント単位なので、ab_glyph と同じ係数でピクセルに直す
+        let (sx, sy) = (font.h_scale_factor(), font.v_scale_factor());
+
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(run);
+        buffer.guess_segment_properties();
+        let shaped = rustybuzz::shape(&face, features, buffer);
+
+        for (info, pos) in shaped.glyph_infos().iter().zip(shaped.glyph_positions()) {
+            let id = GlyphId(info.glyph_id as u16);
+            let gx = x + pos.x_offset as f32 * sx;
+            let gy = ascent - pos.y_offset as f32 * sy;
+            glyphs.push(PlacedGlyph {
+                font: i,
+                glyph: id.with_scale_and_position(scale, point(gx, gy)),
+            });
+            x += pos.x_advance as f32 * sx;
+        }
+    }
+    (glyphs, x)
+}
+
+/// Lay `text` out as one vertical column centred on x = 0, starting at y = 0.
+/// Returns the glyphs and the column's advance height.
+///
+/// The shaper positions each glyph relative to its vertical origin (top
+/// centre) and already folds the shift back to the horizontal origin into the
+/// offsets, so they can be drawn like horizontal glyphs.
+fn layout_column(
+    fonts: &[Arc<LoadedFont>],
+    text: &str,
+    scale: f32,
+    features: &[Feature],
+) -> (Vec<PlacedGlyph>, f32) {
+    let scale = PxScale::from(scale);
+
+    let mut y = 0.0_f32;
+    let mut glyphs = Vec::new();
+    for (i, run) in font_runs(fonts, text) {
+        let font = fonts[i].font.as_scaled(scale);
+        let Some(face) = rustybuzz::Face::from_slice(fonts[i].data(), fonts[i].index) else {
+            continue;
+        };
+        let (sx, sy) = (font.h_scale_factor(), font.v_scale_factor());
+
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(run);
+        buffer.set_direction(Direction::TopToBottom);
+        buffer.guess_segment_properties();
+        let shaped = rustybuzz::shape(&face, features, buffer);
+
+        for (info, pos) in shaped.glyph_infos().iter().zip(shaped.glyph_positions()) {
+            let id = GlyphId(info.glyph_id as u16);
+            // フォント単位は上向きが正、ピクセルは下向きが正
+            let gx = pos.x_offset as f32 * sx;
+            let gy = y - pos.y_offset as f32 * sy;
+            glyphs.push(PlacedGlyph {
+                font: i,
+                glyph: id.with_scale_and_position(scale, point(gx, gy)),
+            });
+            y -= pos.y_advance as f32 * sy;
+        }
+    }
+    (glyphs, y)
+}
@@ -0,0 +1,44 @@
+//! Tests for the background plate: its shapes and the transparent canvas
+//! around them.
+
+use icon_marker::{font, render_with_fonts, BackgroundShape, Color, Fill, IconSpec, Pattern};
+use image::RgbaImage;
+use std::sync::Arc;
+
+fn spec() -> IconSpec {
+    IconSpec {
+        text: "G".to_string(),
+        background: Fill::Solid {
+            color: Color::rgb(255, 255, 255),
+        },
+        pattern: Pattern::None,
+        ..IconSpec::default()
+    }
+}
+
+fn render(spec: &IconSpec) -> RgbaImage {
+    render_with_fonts(spec, &[Arc::new(font::bundled_font())], 64)
+}
+
+#[test]
+fn background_shapes_leave_corners_transparent() {
+    for shape in [
+        BackgroundShape::Rounded,
+        BackgroundShape::Circle,
+        BackgroundShape::Squircle,
+        BackgroundShape::None,
+    ] {
+        let img = render(&IconSpec {
+            bg_shape: shape,
+            ..spec()
+        });
+        assert_eq!(img.get_pixel(0, 0)[3], 0, "{shape:?}: corner");
+        // 文字の縁は透明な下地の上でも半透明になる
+        assert!(
+            img.pixels().any(|p| p[3] > 0 && p[3] < 255),
+            "{shape:?}: no anti-aliased edge"
+        );
+    }
+    let square = render(&spec());
+    assert!(square.pixels().all(|p| p[3] == 255));
+}
@@ -0,0 +1,64 @@
+//! Tests for the bevel and the extrusion of the text.
+
+use icon_marker::{
+    font, render_with_fonts, BackgroundShape, Bevel, Color, Extrusion, Fill, IconSpec, Pattern,
+};
+use image::RgbaImage;
+use std::sync::Arc;
+
+fn spec() -> IconSpec {
+    IconSpec {
+        text: "I".to_string(),
+        fill: Fill::Solid {
+            color: Color::rgb(128, 128, 128),
+        },
+        bg_shape: BackgroundShape::None,
+        pattern: Pattern::None,
+        ..IconSpec::default()
+    }
+}
+
+fn render(spec: &IconSpec) -> RgbaImage {
+    render_with_fonts(spec, &[Arc::new(font::bundled_font())], 64)
+}
+
+#[test]
+fn bevel_lights_one_side_and_shades_the_other() {
+    let img = render(&IconSpec {
+        bevel: Bevel {
+            size: 0.03,
+            ..Bevel::default()
+        },
+        ..spec()
+    });
+    let opaque = || img.pixels().filter(|p| p[3] == 255);
+    assert!(opaque().any(|p| p[0] > 140), "no highlight");
+    assert!(opaque().any(|p| p[0] < 116), "no shadow");
+}
+
+#[test]
+fn extrusion_extends_below_and_right() {
+    let blue = Color::rgb(0, 0, 255);
+    let img = render(&IconSpec {
+        extrusion: Extrusion {
+            depth: 0.1,
+            color: blue,
+            shade: 0.0,
+            ..Extrusion::default()
+        },
+        ..spec()
+    });
+    let (mut max_x, mut max_y) = (0, 0);
+    let (mut grey_x, mut grey_y) = (0, 0);
+    for (x, y, p) in img.enumerate_pixels().filter(|(_, _, p)| p[3] == 255) {
+        if p.0[..3] == [0, 0, 255] {
+            (max_x, max_y) = (max_x.max(x), max_y.max(y));
+        } else if p.0[..3] == [128, 128, 128] {
+            (grey_x, grey_y) = (grey_x.max(x), grey_y.max(y));
+        }
+    }
+    assert!(
+        max_x > grey_x && max_y > grey_y,
+        "extrusion not below and right"
+    );
+}
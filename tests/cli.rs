@@ -0,0 +1,87 @@
+//! Tests for the `render` subcommand, run through the built executable.
+
+use icon_marker::png_meta::read_spec;
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn render(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_icon-marker"))
+        .arg("render")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// A path in the temp directory that no other test process uses.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("icon_marker_{}_{name}", std::process::id()))
+}
+
+#[test]
+fn usage_errors_exit_with_2() {
+    let out = render(&["--bogus", "--out", "x.png"]);
+    assert_eq!(out.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("unexpected argument \"--bogus\""),
+        "{stderr}"
+    );
+    assert!(stderr.contains("Usage:"), "{stderr}");
+
+    assert_eq!(render(&["--text", "A"]).status.code(), Some(2));
+    assert_eq!(render(&["--padding"]).status.code(), Some(2));
+    let style = render(&["--font-style", "Bold", "--out", "x.png"]);
+    assert_eq!(style.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&style.stderr).contains("needs --font-family"));
+}
+
+#[test]
+fn failures_exit_with_1() {
+    let missing = temp_path("missing_font.ttf");
+    let out = temp_path("missing_font.png");
+    let status = render(&[
+        "--font-file",
+        missing.to_str().unwrap(),
+        "--out",
+        out.to_str().unwrap(),
+    ]);
+    assert_eq!(status.status.code(), Some(1));
+    assert!(!out.exists());
+}
+
+#[test]
+fn text_escapes_become_line_breaks_and_backslashes() {
+    let out = temp_path("escapes.png");
+    let status = render(&[
+        "--text",
+        r"a\nb\\n\x",
+        "--sizes",
+        "16",
+        "--out",
+        out.to_str().unwrap(),
+    ]);
+    assert_eq!(status.status.code(), Some(0), "{status:?}");
+    let spec = read_spec(File::open(&out).unwrap()).unwrap();
+    std::fs::remove_file(&out).unwrap();
+    assert_eq!(spec.text, "a\nb\\n\\x");
+}
+
+#[test]
+fn fill_options_need_a_matching_fill() {
+    let out = render(&["--pattern-colors", "#FFFFFF,#000000", "--out", "x.png"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("not a pattern"));
+
+    let solid = [
+        "--fill-color",
+        "#FF0000",
+        "--grad-end",
+        "#0000FF",
+        "--out",
+        "x.png",
+    ];
+    let out = render(&solid);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("not a gradient"));
+}
@@ -0,0 +1,91 @@
+//! Tests for fills: gradients of every kind and four-corner meshes, drawn
+//! on the background plate.
+
+use icon_marker::{
+    font, render_with_fonts, BackgroundShape, Color, Fill, Gradient, GradientKind, GradientSpace,
+    GradientStop, IconSpec, MeshGradient, Pattern,
+};
+use image::{Rgba, RgbaImage};
+use std::sync::Arc;
+
+const RED: Color = Color::rgb(255, 0, 0);
+const GREEN: Color = Color::rgb(0, 255, 0);
+const BLUE: Color = Color::rgb(0, 0, 255);
+
+/// An icon that is only the square plate filled with `background`.
+fn plate(background: Fill) -> RgbaImage {
+    let spec = IconSpec {
+        text: String::new(),
+        background,
+        bg_shape: BackgroundShape::Square,
+        pattern: Pattern::None,
+        ..IconSpec::default()
+    };
+    render_with_fonts(&spec, &[Arc::new(font::bundled_font())], 64)
+}
+
+fn gradient(kind: GradientKind, angle: f32) -> Fill {
+    Fill::Gradient(Gradient {
+        kind,
+        angle,
+        space: GradientSpace::Srgb,
+        stops: vec![
+            GradientStop::new(0.0, RED),
+            GradientStop::new(0.5, GREEN),
+            GradientStop::new(1.0, BLUE),
+        ],
+    })
+}
+
+fn assert_near(actual: Rgba<u8>, expected: Color, tolerance: u8) {
+    let expected = [expected.r, expected.g, expected.b];
+    assert!(
+        actual.0[..3]
+            .iter()
+            .zip(expected)
+            .all(|(a, e)| a.abs_diff(e) <= tolerance),
+        "{actual:?} is not near {expected:?}"
+    );
+}
+
+#[test]
+fn linear_gradient_passes_through_its_stops() {
+    let img = plate(gradient(GradientKind::Linear, 90.0));
+    assert_near(*img.get_pixel(0, 32), RED, 8);
+    assert_near(*img.get_pixel(32, 32), GREEN, 8);
+    assert_near(*img.get_pixel(63, 32), BLUE, 8);
+    // 90° は左から右なので、縦には変わらない
+    assert_eq!(img.get_pixel(10, 0), img.get_pixel(10, 63));
+}
+
+#[test]
+fn radial_gradient_starts_at_the_centre() {
+    let img = plate(gradient(GradientKind::Radial, 0.0));
+    assert_near(*img.get_pixel(32, 32), RED, 8);
+    assert_near(*img.get_pixel(0, 0), BLUE, 8);
+    assert_eq!(img.get_pixel(0, 0), img.get_pixel(63, 63));
+}
+
+#[test]
+fn conic_gradient_turns_around_the_centre() {
+    let img = plate(gradient(GradientKind::Conic, 0.0));
+    let (up, right, down) = (
+        img.get_pixel(32, 4),
+        img.get_pixel(60, 32),
+        img.get_pixel(32, 60),
+    );
+    assert!(up != right && right != down && up != down);
+}
+
+#[test]
+fn mesh_takes_each_corner_colour() {
+    let white = Color::rgb(255, 255, 255);
+    let img = plate(Fill::Mesh(MeshGradient {
+        corners: [RED, GREEN, BLUE, white],
+        space: GradientSpace::Srgb,
+    }));
+    assert_near(*img.get_pixel(0, 0), RED, 8);
+    assert_near(*img.get_pixel(63, 0), GREEN, 8);
+    assert_near(*img.get_pixel(63, 63), BLUE, 8);
+    assert_near(*img.get_pixel(0, 63), white, 8);
+}
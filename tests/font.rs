@@ -0,0 +1,14 @@
+//! Tests for font resolution.
+
+use icon_marker::font::{self, BUNDLED_FONT};
+use icon_marker::FontSpec;
+
+#[test]
+fn default_font_is_the_embedded_copy() {
+    // インストール済みの Aoboshi One があっても埋め込み版を使う
+    assert_eq!(font::default_font().data(), BUNDLED_FONT);
+    assert_eq!(
+        font::resolve(&FontSpec::default()).unwrap().data(),
+        BUNDLED_FONT
+    );
+}
@@ -0,0 +1,164 @@
+//! Golden-image tests for colour blending and gradient interpolation.
+//!
+//! Images are rendered with the bundled font so that they do not depend on
+//! the fonts installed on the machine. Run with `UPDATE_GOLDEN=1` to rewrite
+//! the files in `tests/golden/` after an intended change in rendering.
+
+use icon_marker::color::{blend, mix};
+use icon_marker::{
+    font, render_with_fonts, Blending, Color, Fill, Gradient, GradientSpace, IconSpec, Pattern,
+};
+use image::{Rgba, RgbaImage};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Largest per-channel difference tolerated against a golden image, to allow
+/// for floating-point differences between platforms.
+const TOLERANCE: u8 = 2;
+
+const BLUE: Color = Color::rgb(0, 0, 255);
+const YELLOW: Color = Color::rgb(255, 255, 0);
+
+fn spec(gradient_space: GradientSpace, blending: Blending) -> IconSpec {
+    IconSpec {
+        text: "G".to_string(),
+        background: Fill::Solid {
+            color: Color::rgb(255, 255, 255),
+        },
+        fill: Fill::Gradient(Gradient {
+            space: gradient_space,
+            ..Gradient::two_color(BLUE, YELLOW, 45.0)
+        }),
+        blending,
+        pattern: Pattern::None,
+        ..IconSpec::default()
+    }
+}
+
+fn render(spec: &IconSpec) -> RgbaImage {
+    render_with_fonts(spec, &[Arc::new(font::bundled_font())], 64)
+}
+
+fn check_golden(name: &str, actual: &RgbaImage) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        actual.save(&path).unwrap();
+        return;
+    }
+    let expected = image::open(&path)
+        .unwrap_or_else(|e| panic!("{}: {e} (run with UPDATE_GOLDEN=1)", path.display()))
+        .to_rgba8();
+    assert_eq!(expected.dimensions(), actual.dimensions(), "{name}: size");
+    let worst = expected
+        .pixels()
+        .zip(actual.pixels())
+        .flat_map(|(e, a)| (0..4).map(move |i| e[i].abs_diff(a[i])))
+        .max()
+        .unwrap_or(0);
+    if worst > TOLERANCE {
+        let out = std::env::temp_dir().join(format!("{name}.actual.png"));
+        actual.save(&out).unwrap();
+        panic!(
+            "{name}: differs from golden by up to {worst}; actual image written to {}",
+            out.display()
+        );
+    }
+}
+
+#[test]
+fn golden_gamma_srgb() {
+    check_golden(
+        "gamma_srgb",
+        &render(&spec(GradientSpace::Srgb, Blending::Gamma)),
+    );
+}
+
+#[test]
+fn golden_linear_blending() {
+    check_golden(
+        "linear_srgb",
+        &render(&spec(GradientSpace::Srgb, Blending::Linear)),
+    );
+}
+
+#[test]
+fn golden_linear_gradient() {
+    check_golden(
+        "linear_linear",
+        &render(&spec(GradientSpace::Linear, Blending::Linear)),
+    );
+}
+
+#[test]
+fn golden_oklab_gradient() {
+    check_golden(
+        "linear_oklab",
+        &render(&spec(GradientSpace::Oklab, Blending::Linear)),
+    );
+}
+
+fn spread(c: Color) -> u8 {
+    c.r.max(c.g).max(c.b) - c.r.min(c.g).min(c.b)
+}
+
+#[test]
+fn srgb_midpoint_is_grey_and_oklab_is_not() {
+    let srgb = mix(BLUE, YELLOW, 0.5, GradientSpace::Srgb);
+    assert_eq!(srgb, Color::rgb(127, 127, 127));
+
+    // 線形 RGB でも灰色だが、sRGB のような暗い濁りは無い
+    let linear = mix(BLUE, YELLOW, 0.5, GradientSpace::Linear);
+    assert!(
+        linear.r > srgb.r,
+        "linear midpoint {linear} is not brighter"
+    );
+
+    let oklab = mix(BLUE, YELLOW, 0.5, GradientSpace::Oklab);
+    assert!(spread(oklab) > 64, "OKLab midpoint {oklab} is grey");
+}
+
+#[test]
+fn gradient_endpoints_are_exact() {
+    for space in GradientSpace::ALL {
+        assert_eq!(mix(BLUE, YELLOW, 0.0, space), BLUE, "{space:?}");
+        assert_eq!(mix(BLUE, YELLOW, 1.0, space), YELLOW, "{space:?}");
+    }
+}
+
+#[test]
+fn linear_blending_keeps_half_coverage_bright() {
+    let black = Rgba([0, 0, 0, 255]);
+    let white = Color::rgb(255, 255, 255);
+    assert_eq!(
+        blend(black, white, 0.5, Blending::Gamma),
+        Rgba([127, 127, 127, 255])
+    );
+    // 線形光では 50% の被覆は sRGB で約 188 になる
+    assert_eq!(
+        blend(black, white, 0.5, Blending::Linear),
+        Rgba([188, 188, 188, 255])
+    );
+}
+
+#[test]
+fn blending_modes_render_differently() {
+    let base = render(&spec(GradientSpace::Srgb, Blending::Gamma));
+    for (space, blending) in [
+        (GradientSpace::Srgb, Blending::Linear),
+        (GradientSpace::Oklab, Blending::Linear),
+    ] {
+        let other = render(&spec(space, blending));
+        let changed = base
+            .pixels()
+            .zip(other.pixels())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(
+            changed > 0,
+            "{space:?}/{blending:?} renders like the default"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Tests for the layer stack: order, visibility, opacity, blend modes and
+//! image layers.
+
+use icon_marker::color::blend_mode;
+use icon_marker::{
+    font, render_with_fonts, BackgroundShape, BlendMode, Color, Effect, EffectKind, Fill, IconSpec,
+    ImageFit, ImageLayer, Layer, LayerContent, Pattern,
+};
+use image::{Rgba, RgbaImage};
+use std::sync::Arc;
+
+fn spec() -> IconSpec {
+    IconSpec {
+        text: "G".to_string(),
+        background: Fill::Solid {
+            color: Color::rgb(255, 255, 255),
+        },
+        pattern: Pattern::None,
+        ..IconSpec::default()
+    }
+}
+
+fn render(spec: &IconSpec) -> RgbaImage {
+    render_with_fonts(spec, &[Arc::new(font::bundled_font())], 64)
+}
+
+#[test]
+fn blend_modes_keep_their_neutral_colours() {
+    let dst = Rgba([40, 120, 200, 255]);
+    let same = Color::rgb(40, 120, 200);
+    assert_eq!(
+        blend_mode(BlendMode::Multiply, dst, Color::rgb(255, 255, 255)),
+        same
+    );
+    assert_eq!(
+        blend_mode(BlendMode::Screen, dst, Color::rgb(0, 0, 0)),
+        same
+    );
+    // 透明な下地では元の色のまま
+    let red = Color::rgb(255, 0, 0);
+    assert_eq!(blend_mode(BlendMode::Overlay, Rgba([0, 0, 0, 0]), red), red);
+}
+
+#[test]
+fn contained_image_layer_keeps_its_aspect_ratio() {
+    // 並行して走るテストとぶつからないよう、プロセスごとに別の名前にする
+    let path = std::env::temp_dir().join(format!(
+        "icon_marker_{}_contained_image_layer.png",
+        std::process::id()
+    ));
+    RgbaImage::from_pixel(64, 32, Rgba([255, 0, 0, 255]))
+        .save(&path)
+        .unwrap();
+    let image = ImageLayer {
+        path: path.clone(),
+        fit: ImageFit::Contain,
+        ..ImageLayer::default()
+    };
+    let mut spec = spec();
+    spec.insert_layer(Layer::new(LayerContent::Image { image }), true);
+    let img = render(&spec);
+    std::fs::remove_file(&path).unwrap();
+    let size = img.width();
+    let white = Rgba([255, 255, 255, 255]);
+    let red = Rgba([255, 0, 0, 255]);
+    // 横長の画像は上下 1/4 ずつ背景が残る
+    assert_eq!(*img.get_pixel(size / 2, 2), white);
+    assert_eq!(*img.get_pixel(size / 2, size / 2), red);
+    assert_eq!(*img.get_pixel(size / 2, size - 3), white);
+}
+
+#[test]
+fn layer_stack_order_and_visibility() {
+    let base = spec();
+    let plain = render(&IconSpec {
+        text: String::new(),
+        ..base.clone()
+    });
+    // 文字を隠すか板の下に回すと、文字の無いアイコンと同じになる
+    let mut hidden = base.clone();
+    hidden.layers[2].visible = false;
+    assert_eq!(render(&hidden), plain);
+    let mut below = base.clone();
+    below.layers.swap(0, 2);
+    assert_eq!(render(&below), plain);
+
+    let faded = render(&IconSpec {
+        layers: base
+            .layers
+            .iter()
+            .map(|l| Layer {
+                opacity: if l.content == LayerContent::Text {
+                    0.5
+                } else {
+                    1.0
+                },
+                ..l.clone()
+            })
+            .collect(),
+        ..base.clone()
+    });
+    assert_ne!(faded, render(&base));
+    assert_ne!(faded, plain);
+}
+
+#[test]
+fn clipped_effect_layers_stay_on_the_plate() {
+    let mut circle = IconSpec {
+        bg_shape: BackgroundShape::Circle,
+        padding: 0.0,
+        ..spec()
+    };
+    let shadow = Effect {
+        offset: [0.3, 0.3],
+        spread: Effect::MAX_SPREAD,
+        opacity: 1.0,
+        ..Effect::new(EffectKind::DropShadow)
+    };
+    circle.insert_layer(Layer::new(LayerContent::Effect { effect: shadow }), false);
+    let corner = |img: &RgbaImage| img.get_pixel(62, 62)[3];
+    assert!(corner(&render(&circle)) > 0);
+
+    let effect = circle.layers.iter_mut().find(|l| !l.is_builtin()).unwrap();
+    effect.clip = true;
+    assert_eq!(corner(&render(&circle)), 0);
+}
@@ -0,0 +1,60 @@
+//! Tests for the background patterns and how they scale with the icon.
+
+use icon_marker::{
+    font, render_with_fonts, BackgroundShape, Color, Fill, IconSpec, Pattern, PatternParams,
+};
+use image::{imageops, RgbaImage};
+use std::sync::Arc;
+
+/// An icon that is only the grey square plate with `pattern` on it.
+fn plate(pattern: Pattern, params: PatternParams, size: u32) -> RgbaImage {
+    let spec = IconSpec {
+        text: String::new(),
+        background: Fill::Solid {
+            color: Color::rgb(128, 128, 128),
+        },
+        bg_shape: BackgroundShape::Square,
+        pattern,
+        pattern_params: params,
+        ..IconSpec::default()
+    };
+    render_with_fonts(&spec, &[Arc::new(font::bundled_font())], size)
+}
+
+#[test]
+fn every_pattern_draws_something() {
+    let none = plate(Pattern::None, PatternParams::default(), 128);
+    for pattern in Pattern::ALL.into_iter().filter(|&p| p != Pattern::None) {
+        assert!(
+            plate(pattern, PatternParams::default(), 128) != none,
+            "{pattern:?} draws nothing"
+        );
+    }
+}
+
+#[test]
+fn small_icons_show_the_pattern_at_its_minimum_size_scale() {
+    let params = PatternParams {
+        min_size: 128,
+        ..PatternParams::default()
+    };
+    for pattern in [Pattern::Stripes, Pattern::Grid, Pattern::Dots] {
+        // 64px では 128px の模様の中央を切り出したものと同じになる
+        let large = plate(pattern, params.clone(), 128);
+        let centre = imageops::crop_imm(&large, 32, 32, 64, 64).to_image();
+        assert!(plate(pattern, params.clone(), 64) == centre, "{pattern:?}");
+    }
+}
+
+#[test]
+fn hidden_small_patterns_leave_the_plate_plain() {
+    let params = PatternParams {
+        hide_small: true,
+        ..PatternParams::default()
+    };
+    for size in [32, 64, 128, 256] {
+        let none = plate(Pattern::None, params.clone(), size);
+        let hidden = plate(Pattern::Stripes, params.clone(), size) == none;
+        assert_eq!(hidden, size < params.min_size, "at {size}");
+    }
+}
@@ -0,0 +1,41 @@
+//! Tests for the project settings embedded in exported PNG files.
+
+use std::io::Cursor;
+
+use icon_marker::png_meta::{read_spec, write_png, PngMetaError};
+use icon_marker::{IconSpec, Pattern};
+use image::{Rgba, RgbaImage};
+
+#[test]
+fn written_png_carries_its_spec() {
+    let spec = IconSpec {
+        text: "メタ".to_string(),
+        padding: 0.25,
+        pattern: Pattern::None,
+        ..IconSpec::default()
+    };
+    let img = RgbaImage::from_fn(8, 8, |x, y| Rgba([x as u8 * 30, y as u8 * 30, 90, 255]));
+
+    let mut buf = Cursor::new(Vec::new());
+    write_png(&mut buf, &img, &spec).unwrap();
+    buf.set_position(0);
+    assert_eq!(read_spec(&mut buf).unwrap(), spec);
+
+    // 設定を埋め込んでも普通の PNG として読める
+    let decoded = image::load_from_memory(buf.get_ref()).unwrap().to_rgba8();
+    assert_eq!(decoded, img);
+}
+
+#[test]
+fn plain_png_has_no_spec() {
+    let img = RgbaImage::from_pixel(4, 4, Rgba([1, 2, 3, 255]));
+    let mut buf = Cursor::new(Vec::new());
+    img.write_to(&mut buf, image::ImageFormat::Png).unwrap();
+    buf.set_position(0);
+    assert!(matches!(read_spec(buf), Err(PngMetaError::NoSpec)));
+}
+
+#[test]
+fn non_png_data_is_an_error() {
+    assert!(read_spec(Cursor::new(b"not a png".to_vec())).is_err());
+}
@@ -0,0 +1,237 @@
+//! Tests for project files: round trips, errors and the migration of every
+//! older schema version.
+
+use icon_marker::project::{self, ProjectError, CURRENT_VERSION};
+use icon_marker::{
+    BackgroundShape, BlendMode, Color, Effect, EffectKind, Fill, Gradient, GradientKind,
+    GradientSpace, GradientStop, IconSpec, ImageFit, ImageLayer, Layer, LayerContent, Pattern,
+};
+
+fn solid(hex: &str) -> Fill {
+    Fill::Solid {
+        color: hex.parse().unwrap(),
+    }
+}
+
+#[test]
+fn v1_two_colour_gradient_is_migrated() {
+    let spec = project::from_str(
+        r##"
+text = "A"
+padding = 0.2
+bg_color = "#102030"
+grad_start = "#FF0000"
+grad_end = "#0000FF"
+gradient_space = "oklab"
+"##,
+    )
+    .unwrap();
+    assert_eq!(spec.text, "A");
+    assert_eq!(spec.padding, 0.2);
+    assert_eq!(spec.background, solid("#102030"));
+    assert_eq!(
+        spec.fill,
+        Fill::Gradient(Gradient {
+            space: GradientSpace::Oklab,
+            ..Gradient::two_color(Color::rgb(255, 0, 0), Color::rgb(0, 0, 255), 45.0)
+        })
+    );
+    assert_eq!(spec.layers, Layer::default_stack());
+}
+
+#[test]
+fn v2_gradient_table_becomes_the_text_fill() {
+    let spec = project::from_str(
+        r##"
+version = 2
+text = "B"
+bg_color = "#FFFFFF"
+
+[gradient]
+kind = "radial"
+angle = 90.0
+
+[[gradient.stops]]
+offset = 0.0
+color = "#000000"
+
+[[gradient.stops]]
+offset = 0.5
+color = "#FF8800"
+"##,
+    )
+    .unwrap();
+    assert_eq!(spec.text, "B");
+    assert_eq!(spec.background, solid("#FFFFFF"));
+    assert_eq!(
+        spec.fill,
+        Fill::Gradient(Gradient {
+            kind: GradientKind::Radial,
+            angle: 90.0,
+            space: GradientSpace::Srgb,
+            stops: vec![
+                GradientStop::new(0.0, Color::rgb(0, 0, 0)),
+                GradientStop::new(0.5, Color::rgb(255, 136, 0)),
+            ],
+        })
+    );
+}
+
+#[test]
+fn v3_background_colour_becomes_a_solid_fill() {
+    let spec = project::from_str(
+        r##"
+version = 3
+text = "C"
+bg_color = "#123456"
+pattern = "none"
+
+[fill]
+type = "solid"
+color = "#ABCDEF"
+"##,
+    )
+    .unwrap();
+    assert_eq!(spec.text, "C");
+    assert_eq!(spec.pattern, Pattern::None);
+    assert_eq!(spec.background, solid("#123456"));
+    assert_eq!(spec.fill, solid("#ABCDEF"));
+    assert_eq!(spec.layers, Layer::default_stack());
+}
+
+#[test]
+fn v4_effects_and_images_become_layers() {
+    let spec = project::from_str(
+        r##"
+version = 4
+text = "D"
+bg_shape = "rounded"
+
+[background]
+type = "solid"
+color = "#F0F0F0"
+
+[[effects]]
+kind = "inner-shadow"
+opacity = 0.4
+
+[[effects]]
+kind = "drop-shadow"
+opacity = 0.7
+
+[[images]]
+path = "behind.png"
+opacity = 0.5
+blend = "multiply"
+
+[[images]]
+path = "front.png"
+fit = "contain"
+scale = 0.3
+above_text = true
+"##,
+    )
+    .unwrap();
+    assert_eq!(spec.text, "D");
+    assert_eq!(spec.bg_shape, BackgroundShape::Rounded);
+    assert_eq!(spec.background, solid("#F0F0F0"));
+
+    let image = |path: &str| ImageLayer {
+        path: path.into(),
+        ..ImageLayer::default()
+    };
+    let effect = |kind, opacity| LayerContent::Effect {
+        effect: Effect {
+            kind,
+            opacity,
+            ..Effect::default()
+        },
+    };
+    // v4 の描く順番：板・模様・背面の画像・影・文字・内側の影・前面の画像
+    let expected = vec![
+        Layer::new(LayerContent::Plate),
+        Layer::new(LayerContent::Pattern),
+        Layer {
+            opacity: 0.5,
+            blend: BlendMode::Multiply,
+            clip: true,
+            ..Layer::new(LayerContent::Image {
+                image: image("behind.png"),
+            })
+        },
+        Layer::new(effect(EffectKind::DropShadow, 0.7)),
+        Layer::new(LayerContent::Text),
+        Layer::new(effect(EffectKind::InnerShadow, 0.4)),
+        Layer::new(LayerContent::Image {
+            image: ImageLayer {
+                fit: ImageFit::Contain,
+                scale: 0.3,
+                ..image("front.png")
+            },
+        }),
+    ];
+    assert_eq!(spec.layers, expected);
+}
+
+#[test]
+fn round_trip_keeps_the_spec() {
+    let mut spec = IconSpec {
+        text: "RT\nx".to_string(),
+        background: Fill::Gradient(Gradient {
+            kind: GradientKind::Conic,
+            space: GradientSpace::Linear,
+            ..Gradient::two_color(Color::rgb(1, 2, 3), Color::rgba(200, 100, 50, 128), 30.0)
+        }),
+        bg_shape: BackgroundShape::Squircle,
+        padding: 0.15,
+        pattern: Pattern::Hexagons,
+        ..IconSpec::default()
+    };
+    spec.insert_layer(
+        Layer {
+            opacity: 0.25,
+            blend: BlendMode::SoftLight,
+            visible: false,
+            ..Layer::new(LayerContent::Effect {
+                effect: Effect::new(EffectKind::OuterGlow),
+            })
+        },
+        false,
+    );
+    spec.insert_layer(
+        Layer::new(LayerContent::Image {
+            image: ImageLayer {
+                path: "logo.png".into(),
+                fit: ImageFit::Stretch,
+                offset: [0.1, -0.2],
+                scale: 0.5,
+            },
+        }),
+        true,
+    );
+    let text = project::to_string(&spec).unwrap();
+    assert!(text.starts_with(&format!("version = {CURRENT_VERSION}\n")));
+    assert_eq!(project::from_str(&text).unwrap(), spec);
+}
+
+#[test]
+fn newer_version_is_rejected() {
+    let newer = format!("version = {}\ntext = \"G\"\n", CURRENT_VERSION + 1);
+    assert!(matches!(
+        project::from_str(&newer),
+        Err(ProjectError::UnsupportedVersion(v)) if v == CURRENT_VERSION + 1
+    ));
+}
+
+#[test]
+fn malformed_toml_is_rejected() {
+    assert!(matches!(
+        project::from_str("text = \"unterminated"),
+        Err(ProjectError::Parse(_))
+    ));
+    // 型が合わない値も読み込みエラーになる
+    assert!(matches!(
+        project::from_str(&format!("version = {CURRENT_VERSION}\npadding = \"wide\"")),
+        Err(ProjectError::Parse(_))
+    ));
+}
@@ -0,0 +1,67 @@
+//! Tests for glyph rasterization: anti-aliasing, supersampling and stem
+//! snapping.
+
+use icon_marker::{font, render_with_fonts, BackgroundShape, IconSpec, Pattern};
+use image::RgbaImage;
+use std::sync::Arc;
+
+fn spec(text: &str) -> IconSpec {
+    IconSpec {
+        text: text.to_string(),
+        bg_shape: BackgroundShape::None,
+        pattern: Pattern::None,
+        ..IconSpec::default()
+    }
+}
+
+fn render(spec: &IconSpec, size: u32) -> RgbaImage {
+    render_with_fonts(spec, &[Arc::new(font::bundled_font())], size)
+}
+
+fn is_partial(alpha: u8) -> bool {
+    alpha > 0 && alpha < 255
+}
+
+#[test]
+fn aliased_edges_are_hard() {
+    let aliased = IconSpec {
+        antialias: false,
+        ..spec("G")
+    };
+    let img = render(&aliased, 64);
+    assert!(!img.pixels().any(|p| is_partial(p[3])));
+    assert!(render(&spec("G"), 64).pixels().any(|p| is_partial(p[3])));
+}
+
+#[test]
+fn supersampling_changes_the_coverage() {
+    let with = |supersample| {
+        render(
+            &IconSpec {
+                supersample,
+                ..spec("G")
+            },
+            64,
+        )
+    };
+    assert!(with(4) != with(1));
+}
+
+#[test]
+fn snapped_stems_fill_whole_pixels() {
+    // 縦棒を横切る行で、半端な濃さの画素を数える
+    let partial = |snap_stems| {
+        let img = render(
+            &IconSpec {
+                snap_stems,
+                ..spec("II")
+            },
+            16,
+        );
+        (0..16)
+            .filter(|&x| is_partial(img.get_pixel(x, 8)[3]))
+            .count()
+    };
+    assert_eq!(partial(true), 0);
+    assert!(partial(false) > 0);
+}
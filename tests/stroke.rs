@@ -0,0 +1,65 @@
+//! Tests for the text stroke and its alignment to the glyph outline.
+
+use icon_marker::{
+    font, render_with_fonts, BackgroundShape, Color, Fill, IconSpec, Pattern, Stroke, StrokeAlign,
+};
+use image::RgbaImage;
+use std::sync::Arc;
+
+fn spec(stroke: Stroke) -> IconSpec {
+    IconSpec {
+        text: "I".to_string(),
+        fill: Fill::Solid {
+            color: Color::rgb(128, 128, 128),
+        },
+        stroke,
+        bg_shape: BackgroundShape::None,
+        pattern: Pattern::None,
+        ..IconSpec::default()
+    }
+}
+
+fn stroked(align: StrokeAlign) -> IconSpec {
+    spec(Stroke {
+        width: 0.03,
+        align,
+        fill: Fill::Solid {
+            color: Color::rgb(255, 0, 0),
+        },
+    })
+}
+
+fn render(spec: &IconSpec) -> RgbaImage {
+    render_with_fonts(spec, &[Arc::new(font::bundled_font())], 64)
+}
+
+/// Pixels drawn in `img` where `plain` has nothing.
+fn outside(img: &RgbaImage, plain: &RgbaImage) -> usize {
+    img.pixels()
+        .zip(plain.pixels())
+        .filter(|(p, q)| p[3] > 0 && q[3] == 0)
+        .count()
+}
+
+fn has_red(img: &RgbaImage) -> bool {
+    img.pixels().any(|p| p[0] > 200 && p[1] < 60 && p[2] < 60)
+}
+
+#[test]
+fn stroke_alignment_decides_where_it_is_drawn() {
+    let plain = render(&spec(Stroke::default()));
+    for align in [
+        StrokeAlign::Inside,
+        StrokeAlign::Center,
+        StrokeAlign::Outside,
+    ] {
+        let img = render(&stroked(align));
+        assert!(has_red(&img), "{align:?}: no stroke colour");
+        let spill = outside(&img, &plain);
+        if align == StrokeAlign::Inside {
+            assert_eq!(spill, 0, "inside stroke drawn outside the glyph");
+        } else {
+            assert!(spill > 0, "{align:?}: stroke stays inside the glyph");
+        }
+    }
+}
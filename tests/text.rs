@@ -0,0 +1,158 @@
+//! Tests for text layout: fitting the ink into the padded area, font
+//! fallback, OpenType features, multi-line and vertical text.
+//!
+//! `tests/fonts/Cantarell-Regular.ttf` (SIL OFL, see `OFL.txt` there) is a
+//! Latin-only font for the fallback tests.
+
+use icon_marker::font::{self, LoadedFont};
+use icon_marker::{
+    render_with_fonts, BackgroundShape, FontSpec, IconSpec, Pattern, Stroke, TextAlign, WritingMode,
+};
+use image::{imageops, RgbaImage};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn spec(text: &str) -> IconSpec {
+    IconSpec {
+        text: text.to_string(),
+        bg_shape: BackgroundShape::None,
+        pattern: Pattern::None,
+        ..IconSpec::default()
+    }
+}
+
+fn render(spec: &IconSpec, size: u32) -> RgbaImage {
+    render_with_fonts(spec, &[Arc::new(font::bundled_font())], size)
+}
+
+/// Smallest box `(x0, y0, x1, y1)` of the pixels with any alpha.
+fn ink_box(img: &RgbaImage) -> (u32, u32, u32, u32) {
+    img.enumerate_pixels()
+        .filter(|(_, _, p)| p[3] > 0)
+        .fold((u32::MAX, u32::MAX, 0, 0), |(x0, y0, x1, y1), (x, y, _)| {
+            (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+        })
+}
+
+#[test]
+fn ink_stays_inside_the_padding() {
+    let stroked = IconSpec {
+        stroke: Stroke {
+            width: 0.05,
+            ..Stroke::default()
+        },
+        ..spec("G")
+    };
+    for base in [spec("Icon Marker"), stroked] {
+        for padding in [0.1, 0.2] {
+            let spec = IconSpec {
+                padding,
+                ..base.clone()
+            };
+            for size in [16, 256] {
+                let p = (size as f32 * padding).round() as u32;
+                let (x0, y0, x1, y1) = ink_box(&render(&spec, size));
+                assert!(
+                    x0 >= p && y0 >= p && x1 <= size - 1 - p && y1 <= size - 1 - p,
+                    "{:?} at {size} with padding {padding}: ink {:?}",
+                    spec.text,
+                    (x0, y0, x1, y1)
+                );
+            }
+        }
+    }
+}
+
+fn cantarell() -> Arc<LoadedFont> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fonts/Cantarell-Regular.ttf");
+    font::resolve(&FontSpec::file(path)).unwrap()
+}
+
+#[test]
+fn missing_characters_fall_back_to_the_next_font() {
+    let bundled = vec![Arc::new(font::bundled_font())];
+    let with = |fonts: &[Arc<LoadedFont>], text: &str| render_with_fonts(&spec(text), fonts, 64);
+
+    // Cantarell に仮名はないので、次のフォントで描かれる
+    let chain = [cantarell(), bundled[0].clone()];
+    assert_eq!(with(&chain, "あ"), with(&bundled, "あ"));
+    assert_ne!(with(&chain, "あ"), with(&[cantarell()], "あ"));
+    // 持っている文字は最初のフォントのまま
+    assert_ne!(with(&chain, "Aあ"), with(&bundled, "Aあ"));
+}
+
+#[test]
+fn opentype_features_change_the_glyphs() {
+    let fonts = [Arc::new(font::bundled_font())];
+    let with = |text: &str, tag: &str, on: bool| {
+        let mut spec = spec(text);
+        spec.font_features.insert(tag.to_string(), on);
+        render_with_fonts(&spec, &fonts, 64)
+    };
+    let plain = |text: &str| render_with_fonts(&spec(text), &fonts, 64);
+
+    // 合字は既定で有効なので、切ると形が変わる
+    assert!(with("fi", "liga", false) != plain("fi"));
+    assert!(with("fi", "liga", true) == plain("fi"));
+    assert!(with("1/2", "frac", true) != plain("1/2"));
+}
+
+#[test]
+fn lines_are_aligned_and_spaced() {
+    let lines = |text_align| {
+        let img = render(
+            &IconSpec {
+                text_align,
+                ..spec("I\nIIIII")
+            },
+            64,
+        );
+        let top = imageops::crop_imm(&img, 0, 0, 64, 32).to_image();
+        (ink_box(&top), ink_box(&img))
+    };
+    // 短い 1 行目は揃えの側に寄る
+    let (top, all) = lines(TextAlign::Left);
+    assert_eq!(top.0, all.0);
+    assert!(top.2 < all.2);
+    let (top, all) = lines(TextAlign::Right);
+    assert_eq!(top.2, all.2);
+    assert!(top.0 > all.0);
+    let (top, all) = lines(TextAlign::Center);
+    assert!(top.0 > all.0 && top.2 < all.2);
+
+    // 行の間の空いた行数は行間に比例する
+    let gap = |line_height| {
+        let img = render(
+            &IconSpec {
+                line_height,
+                ..spec("I\nI")
+            },
+            64,
+        );
+        let (_, y0, _, y1) = ink_box(&img);
+        (y0..=y1)
+            .filter(|&y| (0..64).all(|x| img.get_pixel(x, y)[3] == 0))
+            .count()
+    };
+    let (single, double) = (gap(1.0), gap(2.0));
+    assert!(single > 0);
+    assert!(double > single * 3 / 2, "gaps {single} and {double}");
+}
+
+#[test]
+fn vertical_text_stacks_downwards() {
+    let extent = |writing_mode| {
+        let (x0, y0, x1, y1) = ink_box(&render(
+            &IconSpec {
+                writing_mode,
+                ..spec("あいうえ")
+            },
+            64,
+        ));
+        (x1 - x0, y1 - y0)
+    };
+    let (w, h) = extent(WritingMode::Vertical);
+    assert!(h > w * 2, "vertical ink {w}x{h}");
+    let (w, h) = extent(WritingMode::Horizontal);
+    assert!(w > h * 2, "horizontal ink {w}x{h}");
+}